@@ -82,6 +82,35 @@ fn bench_remove(times: usize, limit: usize, removed: usize)
 	println!("        Vec:        {: >10.2?}", dv);
 }
 
+fn bench_reinsert(times: usize, limit: usize, removed: usize)
+{
+	let mut dr = std::time::Duration::new(0, 0);
+	
+	println!("reinsertion of {} elements removed from {} at spread positions ({} times)", removed, limit, times);
+	
+	for _ in 0 .. times
+	{
+		let mut r = Repository::new();
+		for j in 0 .. limit
+		{
+			r.insert(j);
+		}
+		for j in 0 .. removed
+		{
+			r.remove(j * (limit / removed));
+		}
+		dr = dr.saturating_add(timed(||
+		{
+			for j in 0 .. removed
+			{
+				r.insert(j);
+			}
+		}));
+	}
+	
+	println!("        Repository: {: >10.2?}", dr);
+}
+
 fn bench_set_insert(times: usize, limit: usize)
 {
 	println!("insertion of {} elements ({} times)", limit, times);
@@ -143,20 +172,43 @@ fn bench_set_clear_insert(times: usize, limit: usize)
 	}));
 }
 
+fn bench_set_from_unsorted(times: usize, limit: usize)
+{
+	let values = (0 .. limit).map(|j| PRIME.wrapping_mul(j) % limit).collect::<Vec<_>>();
+	println!("construction from {} unsorted elements ({} times)", limit, times);
+	println!("        insert:         {: >10.2?}", repeat(times, ||
+	{
+		let mut s = AATreeSet::new();
+		for &j in values.iter()
+		{
+			s.insert(j);
+		}
+	}));
+	println!("        from_unsorted:  {: >10.2?}", repeat(times, ||
+	{
+		std::hint::black_box(AATreeSet::from_unsorted(values.iter().copied()));
+	}));
+}
+
 fn main()
 {
 	println!("Size of AASet (default): {} bytes", std::mem::size_of::<AATreeSet<i32>>());
 	
 	bench_insert(1_000, 1_000);
 	bench_insert(1_000, 10_000);
+	bench_insert(10, 1_000_000);
 	
 	bench_remove(1_000, 1_000, 400);
 	bench_remove(1_000, 10_000, 400);
 	bench_remove(100, 10_000, 4_000);
 	
+	bench_reinsert(10, 1_000_000, 100_000);
+	
 	bench_set_insert(1_000, 1_000);
 	bench_set_clear_insert(1_000, 1_000);
 	
 	bench_set_insert(1_000, 10_000);
 	bench_set_clear_insert(1_000, 10_000);
+	
+	bench_set_from_unsorted(10, 100_000);
 }
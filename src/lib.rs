@@ -1,3 +1,4 @@
 //! Specialized collections.
 
 pub mod svst;
+pub mod prelude;
@@ -0,0 +1,19 @@
+//! Re-exports of the commonly used collections.
+//! 
+//! ```
+//! use vaults::prelude::*;
+//! 
+//! let mut repository = Repository::new();
+//! let index = repository.insert("value");
+//! 
+//! let mut set = AATreeSet::new();
+//! set.insert(index);
+//! 
+//! let mut map = AATreeMap::new();
+//! map.insert(repository[index], index);
+//! 
+//! assert!(set.contains(&index));
+//! assert_eq!(Some(&index), map.get("value"));
+//! ```
+
+pub use crate::svst::{Repository, Slab, GenRepository, GenKey, AATreeSet, AATreeMap};
@@ -1,9 +1,14 @@
 //! Single vector-storage collections.
 
 pub mod aa;
-pub use repository::Repository;
+pub use repository::{Repository, EntryState, Key};
+pub use slab::Slab;
+pub use gen_repository::{GenRepository, GenKey};
+pub use bit_indexing::{diff, IndexType};
 
 mod repository;
+mod slab;
+mod gen_repository;
 mod vector_storage;
 mod bit_indexing;
 
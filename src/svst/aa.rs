@@ -5,5 +5,6 @@ pub mod tree;
 mod set;
 mod map;
 
+pub use node::Node;
 pub use set::Set;
 pub use map::Map;
@@ -1,5 +1,5 @@
 use crate::svst::aa;
-use crate::svst::aa::node;
+use crate::svst::aa::node::Entry;
 
 #[derive(Debug)]
 pub struct MapEntry<KeyType, MappedType>(KeyType, MappedType);
@@ -8,23 +8,25 @@ impl<KeyType, MappedType> aa::node::Entry for MapEntry<KeyType, MappedType>
 {
 	type Key = KeyType;
 	type Value = (KeyType, MappedType);
+	type ValueRef<'t> = (&'t KeyType, &'t MappedType) where KeyType: 't, MappedType: 't;
 	fn key(&self) -> &Self::Key {&self.0}
 	fn value(self) -> Self::Value {(self.0, self.1)}
+	fn value_ref(&self) -> Self::ValueRef<'_> {(&self.0, &self.1)}
 }
 
 pub type Map<KeyType, MappedType> = aa::tree::Tree<MapEntry<KeyType, MappedType>>;
 
 impl<KeyType, MappedType> Map<KeyType, MappedType>
 {
-	pub fn first_key_value(&self) -> Option<(&KeyType, &MappedType)> {self.impl_first().map(|v| (&v.0, &v.1))}
-	pub fn last_key_value(&self) -> Option<(&KeyType, &MappedType)> {self.impl_last().map(|v| (&v.0, &v.1))}
+	pub fn first_key_value(&self) -> Option<(&KeyType, &MappedType)> {self.impl_first().map(|v| v.value_ref())}
+	pub fn last_key_value(&self) -> Option<(&KeyType, &MappedType)> {self.impl_last().map(|v| v.value_ref())}
 	
 	pub fn contains_key<Key>(&self, key: &Key) -> bool
 	where
 		KeyType: std::borrow::Borrow<Key> + std::cmp::Ord,
 		Key: ?Sized + std::cmp::Ord,
 	{
-		aa::node::find(unsafe {self.repository.as_slice()}, self.root, key).0 != usize::MAX
+		self.find_live(key) != usize::MAX
 	}
 	
 	pub fn get<Key>(&self, key: &Key) -> Option<&MappedType>
@@ -35,12 +37,23 @@ impl<KeyType, MappedType> Map<KeyType, MappedType>
 		self.impl_get(key).map(|v| &v.1)
 	}
 	
+	/// Looks up each of the `keys` independently, the keys may repeat.
+	/// # Time complexity
+	/// _O(N log n)_ where _n_ is the number of entries in the map.
+	pub fn get_many<Key, const N: usize>(&self, keys: [&Key; N]) -> [Option<&MappedType>; N]
+	where
+		KeyType: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: ?Sized + std::cmp::Ord,
+	{
+		keys.map(|key| self.get(key))
+	}
+	
 	pub fn get_mut<Key>(&mut self, key: &Key) -> Option<&mut MappedType>
 	where
 		KeyType: std::borrow::Borrow<Key> + std::cmp::Ord,
 		Key: ?Sized + std::cmp::Ord,
 	{
-		let index = node::find(unsafe {self.repository.as_mut_slice()}, self.root, key).0;
+		let index = self.find_live(key);
 		
 		if index != usize::MAX
 		{
@@ -58,6 +71,33 @@ impl<KeyType, MappedType> Map<KeyType, MappedType>
 		self.impl_get(key).map(|v| (&v.0, &v.1))
 	}
 	
+	pub fn get_key_value_mut<Key>(&mut self, key: &Key) -> Option<(&KeyType, &mut MappedType)>
+	where
+		KeyType: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: ?Sized + std::cmp::Ord,
+	{
+		let index = self.find_live(key);
+		
+		if index != usize::MAX
+		{
+			let entry = self.repository[index].as_mut();
+			return Some((&entry.0, &mut entry.1));
+		}
+		
+		return None;
+	}
+	
+	/// Returns the entry with the `rank`-th smallest key in the map, counting from zero.
+	/// # Time complexity
+	/// _O(log n)_ where _n_ is the number of entries in the map.
+	pub fn select(&self, rank: usize) -> Option<(&KeyType, &MappedType)>
+	{
+		self.impl_select(rank).map(|v| v.value_ref())
+	}
+	
+	/// Inserts the `key` with the `mapped` value, returning the previous value of an equal key if there was one.
+	/// Unlike [BTreeMap::insert](std::collections::BTreeMap::insert), the stored key is replaced by the new `key` as well,
+	/// which matters for keys that compare equal while holding different data.
 	pub fn insert(&mut self, key: KeyType, mapped: MappedType) -> Option<MappedType>
 	where
 		KeyType: std::cmp::Ord
@@ -65,6 +105,137 @@ impl<KeyType, MappedType> Map<KeyType, MappedType>
 		self.try_insert(MapEntry {0: key, 1: mapped}, |v| v.map(|v| v.1))
 	}
 	
+	/// Inserts all the entries of the iterator and returns the number of keys which were not present.
+	/// Values of the iterator replace the values of the keys already present in the map.
+	pub fn insert_all<Iter>(&mut self, iter: Iter) -> usize
+	where
+		KeyType: std::cmp::Ord,
+		Iter: std::iter::IntoIterator<Item = (KeyType, MappedType)>,
+	{
+		let iter = iter.into_iter();
+		self.repository.reserve(iter.size_hint().0);
+		return iter.map(|(key, mapped)| self.insert(key, mapped).is_none() as usize).sum();
+	}
+	
+	/// Returns a mutable reference to the value of the `key`, inserting the default value first if the key is not present.
+	/// # Time complexity
+	/// _O(log n)_ where _n_ is the number of entries in the map.
+	pub fn entry_or_default(&mut self, key: KeyType) -> &mut MappedType
+	where
+		KeyType: std::cmp::Ord,
+		MappedType: Default,
+	{
+		let position = self.find_or_insert_with(key, |key| MapEntry {0: key, 1: MappedType::default()});
+		return &mut self.repository[position].as_mut().1;
+	}
+	
+	/// Returns a mutable reference to the value of the `key`, inserting `default` first if the key is not present.
+	/// # Time complexity
+	/// _O(log n)_ where _n_ is the number of entries in the map.
+	pub fn get_mut_or_insert(&mut self, key: KeyType, default: MappedType) -> &mut MappedType
+	where
+		KeyType: std::cmp::Ord
+	{
+		let position = self.find_or_insert_with(key, |key| MapEntry {0: key, 1: default});
+		return &mut self.repository[position].as_mut().1;
+	}
+	
+	/// Inserts all the entries of an iterator sorted by ascending keys.
+	/// Values of the iterator replace the values of the keys already present in the map.
+	/// 
+	/// Each search starts from the previously inserted entry instead of the root,
+	/// which is faster than individual insertions when merging sorted batches.
+	pub fn extend_from_sorted<Iter>(&mut self, iter: Iter)
+	where
+		KeyType: std::cmp::Ord,
+		Iter: std::iter::IntoIterator<Item = (KeyType, MappedType)>,
+	{
+		let iter = iter.into_iter();
+		self.repository.reserve(iter.size_hint().0);
+		let mut hint = usize::MAX;
+		
+		for (key, mapped) in iter
+		{
+			if hint != usize::MAX
+			{
+				debug_assert!(self.repository[hint].as_ref().0 <= key, "the input is not sorted");
+			}
+			
+			hint = self.insert_after(hint, MapEntry {0: key, 1: mapped});
+		}
+	}
+	
+	/// Constructs a perfectly balanced map from entries with keys which are already sorted and unique, without checking it in release builds.
+	/// If the keys are not strictly ascending, the map is in an unspecified but memory-safe state.
+	/// # Panics
+	/// Panics in debug builds if the keys are not strictly ascending.
+	/// # Time complexity
+	/// _O(n)_.
+	pub fn from_sorted_unique_iter<Iter>(iter: Iter) -> Self
+	where
+		KeyType: std::cmp::Ord,
+		Iter: std::iter::IntoIterator<Item = (KeyType, MappedType)>,
+	{
+		Self::from_sorted_iter(iter.into_iter().map(|(key, mapped)| MapEntry {0: key, 1: mapped}))
+	}
+	
+	/// Moves all the entries of `other` into this map.
+	/// When a key is already present, `combine` is called with the key, the present value and the incoming value
+	/// to fold the incoming value into the present one.
+	pub fn merge<Function>(&mut self, mut other: Self, mut combine: Function)
+	where
+		KeyType: std::cmp::Ord,
+		Function: std::ops::FnMut(&KeyType, &mut MappedType, MappedType),
+	{
+		for (key, mapped) in other.drain()
+		{
+			if let Some((present_key, present)) = self.get_key_value_mut(&key)
+			{
+				combine(present_key, present, mapped);
+			}
+			else
+			{
+				self.insert(key, mapped);
+			}
+		}
+	}
+	
+	/// Moves the entries with keys greater than or equal to `key` to a new map and returns it.
+	/// # Time complexity
+	/// _O(m log n)_ where _m_ is the number of moved entries and _n_ is the number of entries in the map.
+	pub fn split_off<Key>(&mut self, key: &Key) -> Self
+	where
+		KeyType: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: ?Sized + std::cmp::Ord,
+	{
+		let mut moved = Vec::new();
+		
+		while let Some((last, _)) = self.last_key_value()
+		{
+			if last.borrow() < key
+			{
+				break;
+			}
+			
+			moved.extend(self.pop_last());
+		}
+		
+		let mut result = Self::new();
+		result.extend_from_sorted(moved.into_iter().rev());
+		return result;
+	}
+	
+	/// Consumes the map and returns the maps of the entries with keys less than `key`
+	/// and of the entries with keys greater than or equal to `key`.
+	pub fn split_at_key<Key>(mut self, key: &Key) -> (Self, Self)
+	where
+		KeyType: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: ?Sized + std::cmp::Ord,
+	{
+		let upper = self.split_off(key);
+		return (self, upper);
+	}
+	
 	pub fn remove<Key>(&mut self, key: &Key) -> Option<MappedType>
 	where
 		KeyType: std::borrow::Borrow<Key> + std::cmp::Ord,
@@ -78,7 +249,7 @@ impl<KeyType, MappedType> Map<KeyType, MappedType>
 		KeyType: std::borrow::Borrow<Key> + std::cmp::Ord,
 		Key: ?Sized + std::cmp::Ord,
 	{
-		let index = aa::node::find(unsafe {self.repository.as_slice()}, self.root, key).0;
+		let index = self.find_live(key);
 		
 		if index != usize::MAX
 		{
@@ -95,6 +266,192 @@ impl<KeyType, MappedType> Map<KeyType, MappedType>
 	{
 		self.impl_retain(move |v| function(&v.0, &mut v.1));
 	}
+	
+	/// Retains only the entries whose values satisfy the predicate, the keys are not passed to it.
+	pub fn retain_values<Function>(&mut self, mut function: Function)
+	where
+		KeyType: std::cmp::Ord,
+		Function: std::ops::FnMut(&mut MappedType) -> bool,
+	{
+		self.impl_retain(move |v| function(&mut v.1));
+	}
+	
+	/// Returns an iterator which removes the entries satisfying the predicate and yields them in ascending order of the keys.
+	/// The entries for which the predicate returns `false` are kept.
+	/// Dropping the iterator early leaves the entries not yet visited in the map.
+	pub fn drain_filter<'t, Function>(&'t mut self, mut function: Function) -> impl std::iter::Iterator<Item = (KeyType, MappedType)> + 't
+	where Function: std::ops::FnMut(&KeyType, &mut MappedType) -> bool + 't
+	{
+		let mut current = self.first;
+		
+		std::iter::from_fn(move || loop
+		{
+			if current == usize::MAX
+			{
+				return None;
+			}
+			
+			let position = current;
+			
+			// Resolve the successor first, the removal does not move the remaining nodes
+			let mut it = aa::node::Iterator::<&[aa::node::Node<MapEntry<KeyType, MappedType>>]>
+			{
+				first: self.first,
+				last: self.last,
+				bounds: [position, self.last],
+				nodes: unsafe {self.repository.as_slice()},
+			};
+			aa::node::iter_impl!(it, 0);
+			current = it.bounds[0];
+			
+			if ! self.repository[position].tombstone
+			{
+				let entry = self.repository[position].as_mut();
+				
+				if function(&entry.0, &mut entry.1)
+				{
+					return self.remove_at(position);
+				}
+			}
+		})
+	}
+	
+	/// Returns an iterator over the entries with keys contained in `range` in ascending order,
+	/// with mutable references to the values.
+	/// # Time complexity
+	/// _O(log n)_ to find the first entry, where _n_ is the number of entries in the map.
+	pub fn range_mut<'t, Key, Range>(&'t mut self, range: Range) -> impl std::iter::Iterator<Item = (&'t KeyType, &'t mut MappedType)>
+	where
+		KeyType: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: ?Sized + std::cmp::Ord + 't,
+		Range: std::ops::RangeBounds<Key> + 't,
+	{
+		let mut position = self.lower_bound_position(range.start_bound());
+		let nodes = aa::node::RawNodes::new(unsafe {self.repository.as_mut_slice()});
+		
+		std::iter::from_fn(move ||
+		{
+			while position != usize::MAX && unsafe {nodes.tombstone(position)}
+			{
+				position = unsafe {nodes.successor(position)};
+			}
+			
+			if position == usize::MAX
+			{
+				return None;
+			}
+			
+			// Every node is yielded at most once and the links are read without referencing the values,
+			// the references do not alias
+			let entry = unsafe {nodes.value(position)};
+			let (key, mapped) = unsafe {(&*std::ptr::addr_of!((*entry).0), &mut *std::ptr::addr_of_mut!((*entry).1))};
+			
+			if ! range.contains(key.borrow())
+			{
+				position = usize::MAX;
+				return None;
+			}
+			
+			position = unsafe {nodes.successor(position)};
+			return Some((key, mapped));
+		})
+	}
+	
+	/// Consumes the map returning its keys in ascending order.
+	pub fn into_keys(self) -> impl std::iter::DoubleEndedIterator<Item = KeyType> + std::iter::ExactSizeIterator
+	{
+		self.into_iter().map(|(key, _)| key)
+	}
+	
+	/// Consumes the map returning its values in ascending order of their keys.
+	pub fn into_values(self) -> impl std::iter::DoubleEndedIterator<Item = MappedType> + std::iter::ExactSizeIterator
+	{
+		self.into_iter().map(|(_, mapped)| mapped)
+	}
+	
+	pub fn iter<'t>(&'t self) -> aa::node::Iterator<&'t [aa::node::Node<MapEntry<KeyType, MappedType>>]>
+	{
+		aa::node::Iterator::<&'t [aa::node::Node<MapEntry<KeyType, MappedType>>]>
+		{
+			first: self.first,
+			last: self.last,
+			bounds: [self.first, self.last],
+			nodes: unsafe {self.repository.as_slice()},
+		}
+	}
+}
+
+impl<'t, KeyType, MappedType> std::iter::Iterator for aa::node::Iterator<&'t [aa::node::Node<MapEntry<KeyType, MappedType>>]>
+{
+	type Item = (&'t KeyType, &'t MappedType);
+	
+	fn next(&mut self) -> Option<Self::Item>
+	{
+		loop
+		{
+			match aa::node::iter_impl!(self, 0)
+			{
+				usize::MAX => return None,
+				i if self.nodes[i].tombstone => continue,
+				i => return Some(self.nodes[i].as_ref().value_ref()),
+			}
+		}
+	}
+}
+
+impl<'t, KeyType, MappedType> std::iter::DoubleEndedIterator for aa::node::Iterator<&'t [aa::node::Node<MapEntry<KeyType, MappedType>>]>
+{
+	fn next_back(&mut self) -> Option<Self::Item>
+	{
+		loop
+		{
+			match aa::node::iter_impl!(self, 1)
+			{
+				usize::MAX => return None,
+				i if self.nodes[i].tombstone => continue,
+				i => return Some(self.nodes[i].as_ref().value_ref()),
+			}
+		}
+	}
+}
+
+impl<'t, KeyType, MappedType> std::iter::FusedIterator for aa::node::Iterator<&'t [aa::node::Node<MapEntry<KeyType, MappedType>>]>
+{
+}
+
+impl<KeyType, MappedType> PartialEq for Map<KeyType, MappedType>
+where
+	KeyType: PartialEq,
+	MappedType: PartialEq,
+{
+	fn eq(&self, other: &Self) -> bool
+	{
+		self.len() == other.len() && self.iter().eq(other.iter())
+	}
+}
+
+impl<KeyType, MappedType> Eq for Map<KeyType, MappedType>
+where
+	KeyType: Eq,
+	MappedType: Eq,
+{
+}
+
+/// The entries are hashed in ascending order of their keys, equal maps have equal hashes.
+impl<KeyType, MappedType> std::hash::Hash for Map<KeyType, MappedType>
+where
+	KeyType: std::hash::Hash,
+	MappedType: std::hash::Hash,
+{
+	fn hash<Hasher: std::hash::Hasher>(&self, state: &mut Hasher)
+	{
+		state.write_usize(self.len());
+		
+		for entry in self.iter()
+		{
+			entry.hash(state);
+		}
+	}
 }
 
 impl<Key, KeyType, MappedType> std::ops::Index<&Key> for Map<KeyType, MappedType>
@@ -109,3 +466,791 @@ where
 		&self.impl_get(index).expect("no entry found for key").1
 	}
 }
+
+impl<'t, KeyType, MappedType> aa::tree::OccupiedEntry<'t, MapEntry<KeyType, MappedType>>
+{
+	/// Returns a reference to the key of the entry.
+	pub fn key(&self) -> &KeyType
+	{
+		&self.tree.repository[self.position].as_ref().0
+	}
+	
+	/// Returns a mutable reference to the value of the entry.
+	pub fn get_mut(&mut self) -> &mut MappedType
+	{
+		&mut self.tree.repository[self.position].as_mut().1
+	}
+	
+	/// Converts the entry into a mutable reference to its value with the lifetime of the map.
+	pub fn into_mut(self) -> &'t mut MappedType
+	{
+		&mut self.tree.repository[self.position].as_mut().1
+	}
+	
+	/// Removes the entry from the map and returns its value.
+	pub fn remove(self) -> MappedType
+	{
+		self.remove_entry().1
+	}
+	
+	/// Replaces the value of the entry, returning the old value.
+	pub fn insert(&mut self, value: MappedType) -> MappedType
+	{
+		std::mem::replace(self.get_mut(), value)
+	}
+}
+
+impl<'t, KeyType, MappedType> aa::tree::VacantEntry<'t, MapEntry<KeyType, MappedType>>
+{
+	/// Inserts the `value` with the key of the entry and returns a mutable reference to it.
+	pub fn insert(self, value: MappedType) -> &'t mut MappedType
+	{
+		let (tree, position) = self.insert_with(|key| MapEntry {0: key, 1: value});
+		return &mut tree.repository[position].as_mut().1;
+	}
+}
+
+impl<'t, KeyType, MappedType> aa::tree::Entry<'t, MapEntry<KeyType, MappedType>>
+{
+	/// Returns a mutable reference to the value of the entry, inserting `default` first if it is vacant.
+	pub fn or_insert(self, default: MappedType) -> &'t mut MappedType
+	{
+		self.or_insert_with(|| default)
+	}
+	
+	/// Returns a mutable reference to the value of the entry, inserting the result of `function` first if it is vacant.
+	pub fn or_insert_with<Function>(self, function: Function) -> &'t mut MappedType
+	where Function: std::ops::FnOnce() -> MappedType
+	{
+		match self
+		{
+			aa::tree::Entry::Occupied(entry) => entry.into_mut(),
+			aa::tree::Entry::Vacant(entry) => entry.insert(function()),
+		}
+	}
+	
+	/// Calls `function` with the value of the entry if it is occupied.
+	pub fn and_modify<Function>(mut self, function: Function) -> Self
+	where Function: std::ops::FnOnce(&mut MappedType)
+	{
+		if let aa::tree::Entry::Occupied(entry) = &mut self
+		{
+			function(entry.get_mut());
+		}
+		
+		return self;
+	}
+}
+
+#[test]
+fn test_aa_map_iter()
+{
+	let mut map = Map::<i32, char>::new();
+	
+	assert_eq!(None, map.iter().next());
+	
+	for (i, c) in ['d', 'a', 'c', 'b'].into_iter().enumerate()
+	{
+		map.insert(3 - i as i32, c);
+	}
+	
+	assert!([(0, 'b'), (1, 'c'), (2, 'a'), (3, 'd')].iter().map(|(k, v)| (k, v)).eq(map.iter()));
+	assert!([(3, 'd'), (2, 'a'), (1, 'c'), (0, 'b')].iter().map(|(k, v)| (k, v)).eq(map.iter().rev()));
+}
+
+#[test]
+fn test_aa_map_hash()
+{
+	use std::hash::{Hash, Hasher};
+	let hash = |map: &Map<i32, i32>|
+	{
+		let mut hasher = std::collections::hash_map::DefaultHasher::new();
+		map.hash(&mut hasher);
+		hasher.finish()
+	};
+	
+	let mut a = Map::<i32, i32>::new();
+	let mut b = Map::<i32, i32>::new();
+	
+	for i in 0 .. 100
+	{
+		a.insert(i, i * i);
+		b.insert(99 - i, (99 - i) * (99 - i));
+	}
+	
+	assert_eq!(a, b);
+	assert_eq!(hash(&a), hash(&b));
+	
+	b.insert(50, 0);
+	assert_ne!(a, b);
+}
+
+#[test]
+fn test_aa_map_extend_from_sorted()
+{
+	use rand::{Rng, SeedableRng};
+	let mut rng = rand::rngs::SmallRng::seed_from_u64(0);
+	let mut map = Map::<i32, i32>::new();
+	let mut reference = Map::<i32, i32>::new();
+	
+	map.extend_from_sorted((0 .. 50).map(|i| (i * 2, i)));
+	reference.extend_from_sorted([]);
+	for i in 0 .. 50
+	{
+		reference.insert(i * 2, i);
+	}
+	assert_eq!(reference, map);
+	
+	for round in 0 .. 20
+	{
+		let mut batch = (0 .. rng.gen_range(0 .. 200)).map(|_| rng.gen_range(-100 .. 400)).collect::<Vec<_>>();
+		batch.sort();
+		
+		for &key in batch.iter()
+		{
+			reference.insert(key, round);
+		}
+		
+		map.extend_from_sorted(batch.into_iter().map(|key| (key, round)));
+		map.check_invariants();
+		assert_eq!(reference, map);
+	}
+}
+
+#[test]
+fn test_aa_map_value_ref()
+{
+	let entry = MapEntry {0: 1, 1: String::from("a")};
+	assert_eq!((&1, &String::from("a")), entry.value_ref());
+	assert_eq!((1, String::from("a")), entry.value());
+	
+	let mut map = Map::<i32, char>::new();
+	for (k, v) in [(2, 'b'), (1, 'a'), (3, 'c')]
+	{
+		map.insert(k, v);
+	}
+	assert_eq!(Some((&1, &'a')), map.first_key_value());
+	assert_eq!(Some((&3, &'c')), map.last_key_value());
+	assert_eq!(Some((&2, &'b')), map.select(1));
+}
+
+#[test]
+fn test_aa_map_drain()
+{
+	let values = std::rc::Rc::new(());
+	let mut map = Map::<i32, std::rc::Rc<()>>::new();
+	for k in 0 .. 100
+	{
+		map.insert(k, values.clone());
+	}
+	
+	{
+		let mut drain = map.drain();
+		assert_eq!(Some((0, values.clone())), drain.next());
+		assert_eq!(Some((99, values.clone())), drain.next_back());
+	}
+	
+	assert!(map.is_empty());
+	assert_eq!(1, std::rc::Rc::strong_count(&values));
+}
+
+#[test]
+fn test_aa_map_merge()
+{
+	let count = |text: &str|
+	{
+		let mut result = Map::<char, i32>::new();
+		for c in text.chars()
+		{
+			let mut single = Map::new();
+			single.insert(c, 1);
+			result.merge(single, |_, a, b| *a += b);
+		}
+		return result;
+	};
+	
+	let mut counts = count("abracadabra");
+	let other = count("alakazam");
+	assert_eq!(Some(&5), counts.get(&'a'));
+	
+	counts.merge(other, |_, a, b| *a += b);
+	
+	assert!([('a', 9), ('b', 2), ('c', 1), ('d', 1), ('k', 1), ('l', 1), ('m', 1), ('r', 2), ('z', 1)]
+		.iter().map(|(k, v)| (k, v)).eq(counts.iter())
+	);
+	counts.check_invariants();
+}
+
+#[test]
+fn test_aa_map_into_keys_values()
+{
+	let build = |counter: &std::rc::Rc<()>|
+	{
+		let mut map = Map::<String, std::rc::Rc<()>>::new();
+		for k in ["d", "a", "c", "b"]
+		{
+			map.insert(k.to_owned(), counter.clone());
+		}
+		return map;
+	};
+	
+	let counter = std::rc::Rc::new(());
+	
+	assert_eq!(vec!["a", "b", "c", "d"], build(&counter).into_keys().collect::<Vec<_>>());
+	assert_eq!(1, std::rc::Rc::strong_count(&counter));
+	
+	assert_eq!(4, build(&counter).into_values().count());
+	assert_eq!(1, std::rc::Rc::strong_count(&counter));
+	
+	let mut map = Map::<i32, String>::new();
+	for k in [3, 1, 2]
+	{
+		map.insert(k, k.to_string());
+	}
+	assert_eq!(vec!["1", "2", "3"], map.into_values().collect::<Vec<_>>());
+	
+	{
+		let mut keys = build(&counter).into_keys();
+		assert_eq!(Some("d".to_owned()), keys.next_back());
+		assert_eq!(4, std::rc::Rc::strong_count(&counter));
+	}
+	assert_eq!(1, std::rc::Rc::strong_count(&counter));
+}
+
+#[test]
+fn test_aa_map_retain_values()
+{
+	use rand::seq::SliceRandom;
+	let mut keys = (0 .. 1000).collect::<Vec<i32>>();
+	keys.shuffle(&mut rand::thread_rng());
+	let mut map = Map::<i32, i32>::new();
+	for &k in keys.iter()
+	{
+		map.insert(k, k * 10);
+	}
+	
+	let mut visited = 0;
+	map.retain_values(|v|
+	{
+		visited += 1;
+		*v += 1;
+		*v % 30 != 1 && *v != 11 && *v != 9981
+	});
+	
+	assert_eq!(1000, visited);
+	map.check_invariants();
+	assert!((0 .. 1000).filter(|k| k % 3 != 0 && *k != 1 && *k != 998)
+		.map(|k| (k, k * 10 + 1)).eq(map.iter().map(|(k, v)| (*k, *v)))
+	);
+	assert_eq!(Some((&2, &21)), map.first_key_value());
+	assert_eq!(Some((&997, &9971)), map.last_key_value());
+}
+
+#[test]
+fn test_aa_map_split()
+{
+	let mut map = Map::<i32, String>::new();
+	for k in 0 .. 100
+	{
+		map.insert(k, k.to_string());
+	}
+	
+	let (lower, upper) = map.split_at_key(&50);
+	assert!((0 .. 50).map(|k| (k, k.to_string())).eq(lower.iter().map(|(k, v)| (*k, v.clone()))));
+	assert!((50 .. 100).map(|k| (k, k.to_string())).eq(upper.iter().map(|(k, v)| (*k, v.clone()))));
+	lower.check_invariants();
+	upper.check_invariants();
+	
+	let (lower, upper) = upper.split_at_key(&1000);
+	assert_eq!(50, lower.len());
+	assert!(upper.is_empty());
+	
+	let (lower, upper) = lower.split_at_key(&-1);
+	assert!(lower.is_empty());
+	assert_eq!(50, upper.len());
+	
+	let mut map = upper;
+	let tail = map.split_off(&75);
+	assert_eq!(Some((&74, &String::from("74"))), map.last_key_value());
+	assert_eq!(Some((&75, &String::from("75"))), tail.first_key_value());
+	assert_eq!(25, tail.len());
+}
+
+#[test]
+fn test_aa_map_position_of()
+{
+	let mut map = Map::<String, i32>::new();
+	for k in 0 .. 100
+	{
+		map.insert(k.to_string(), k);
+	}
+	
+	assert_eq!(None, map.position_of("100"));
+	let position = map.position_of("42").unwrap();
+	
+	for k in 50 .. 100
+	{
+		map.remove(k.to_string().as_str());
+	}
+	
+	map.impl_at_mut(position).1 = -42;
+	assert_eq!(Some(&-42), map.get("42"));
+	assert_eq!(Some(("42".to_owned(), -42)), map.remove_at(position));
+	assert_eq!(None, map.position_of("42"));
+}
+
+#[test]
+fn test_aa_map_range_mut()
+{
+	let mut map = Map::<i32, i32>::new();
+	for k in (0 .. 100).rev()
+	{
+		map.insert(k, k + 1);
+	}
+	
+	let mut keys = Vec::new();
+	for (k, v) in map.range_mut(20 .. 50)
+	{
+		keys.push(*k);
+		*v = 0;
+	}
+	
+	assert!((20 .. 50).eq(keys));
+	assert!(map.iter().all(|(k, v)| *v == if (20 .. 50).contains(k) {0} else {k + 1}));
+	
+	assert_eq!(0, map.range_mut(200 ..).count());
+	assert_eq!(0, map.range_mut(30 .. 30).count());
+	assert_eq!(100, map.range_mut(..).count());
+	assert!((95 ..= 99).eq(map.range_mut((std::ops::Bound::Excluded(94), std::ops::Bound::Unbounded)).map(|(k, _)| *k)));
+	assert!((0 ..= 3).eq(map.range_mut(..= 3).map(|(k, _)| *k)));
+}
+
+#[test]
+fn test_aa_map_range_mut_collect()
+{
+	let mut map = Map::<i32, i32>::new();
+	for k in 0 .. 20
+	{
+		map.insert(k, k);
+	}
+	map.remove_lazy(&7);
+	
+	let values = map.range_mut(3 .. 15).map(|(_, v)| v).collect::<Vec<_>>();
+	assert_eq!(11, values.len());
+	
+	for v in values
+	{
+		*v *= 10;
+	}
+	
+	assert!(map.iter().all(|(k, v)| *v == if (3 .. 15).contains(k) {k * 10} else {*k}));
+}
+
+#[test]
+fn test_aa_map_get_key_value_mut()
+{
+	let mut map = Map::<String, i32>::new();
+	map.insert(String::from("a"), 1);
+	map.insert(String::from("b"), 2);
+	
+	assert_eq!(None, map.get_key_value_mut("c"));
+	
+	let (key, value) = map.get_key_value_mut("b").unwrap();
+	assert_eq!("b", key);
+	*value += 40;
+	
+	assert_eq!(Some(&42), map.get("b"));
+	assert_eq!(Some(&1), map.get("a"));
+}
+
+#[test]
+fn test_aa_map_reuse_repository()
+{
+	let mut map = Map::<i32, String>::with_repository_capacity(100);
+	assert_eq!(100, map.capacity());
+	for k in 0 .. 100
+	{
+		map.insert(k, k.to_string());
+	}
+	assert_eq!(100, map.capacity());
+	
+	let repository = map.into_repository();
+	let pointer = unsafe {repository.as_slice().as_ptr()};
+	assert_eq!(100, repository.len());
+	
+	let mut map = Map::<i32, String>::from_repository_parts(repository);
+	assert!(map.is_empty());
+	assert_eq!(100, map.capacity());
+	for k in (0 .. 100).rev()
+	{
+		map.insert(k, k.to_string());
+	}
+	map.check_invariants();
+	assert!(map.iter().map(|(k, _)| *k).eq(0 .. 100));
+	
+	let repository = map.into_repository();
+	assert_eq!(100, repository.capacity());
+	assert_eq!(pointer, unsafe {repository.as_slice().as_ptr()});
+}
+
+#[test]
+fn test_aa_map_entry_or_default()
+{
+	let text = "the quick brown fox jumps over the lazy dog the fox";
+	let mut map = Map::<String, usize>::new();
+	for word in text.split(' ')
+	{
+		*map.entry_or_default(word.to_owned()) += 1;
+	}
+	map.check_invariants();
+	
+	let mut expected = std::collections::BTreeMap::<String, usize>::new();
+	for word in text.split(' ')
+	{
+		*expected.entry(word.to_owned()).or_default() += 1;
+	}
+	
+	assert!(expected.iter().eq(map.iter()));
+	assert_eq!(Some(&3), map.get("the"));
+	
+	map.remove_lazy("fox");
+	assert_eq!(0, *map.entry_or_default(String::from("fox")));
+	map.check_invariants();
+	assert_eq!(expected.len(), map.len());
+}
+
+#[test]
+fn test_aa_map_get_mut_or_insert()
+{
+	let mut map = Map::<i32, String>::new();
+	map.insert(1, String::from("one"));
+	
+	let value = map.get_mut_or_insert(1, String::from("default"));
+	assert_eq!("one", value);
+	value.push('!');
+	assert_eq!(Some(&String::from("one!")), map.get(&1));
+	
+	assert_eq!("default", map.get_mut_or_insert(2, String::from("default")));
+	assert_eq!(2, map.len());
+	map.check_invariants();
+	
+	for k in 0 .. 100
+	{
+		*map.get_mut_or_insert(k, String::new()) += "x";
+	}
+	map.check_invariants();
+	assert_eq!(100, map.len());
+	assert_eq!(Some(&String::from("one!x")), map.get(&1));
+	assert_eq!(Some(&String::from("defaultx")), map.get(&2));
+	assert_eq!(Some(&String::from("x")), map.get(&99));
+}
+
+#[test]
+fn test_aa_map_insert_all()
+{
+	let mut map = Map::<i32, i32>::new();
+	assert_eq!(3, map.insert_all([(1, 1), (2, 2), (2, 3), (3, 3), (1, 4)]));
+	assert_eq!(1, map.insert_all([(0, 0), (1, 1)]));
+	map.check_invariants();
+	assert!(map.iter().map(|(&k, &v)| (k, v)).eq([(0, 0), (1, 1), (2, 3), (3, 3)]));
+}
+
+#[test]
+fn test_aa_map_first_last_entry()
+{
+	let mut map = Map::<i32, i32>::new();
+	assert!(map.first_entry().is_none());
+	assert!(map.last_entry().is_none());
+	for k in 0 .. 100
+	{
+		map.insert(k, k % 7);
+	}
+	
+	while let Some(mut entry) = map.first_entry()
+	{
+		if entry.get().1 % 3 != 0
+		{
+			break;
+		}
+		
+		*entry.get_mut() += 1;
+		let (key, value) = entry.remove_entry();
+		assert_eq!(key % 7 + 1, value);
+	}
+	
+	map.check_invariants();
+	assert_eq!(Some((&1, &1)), map.first_key_value());
+	
+	let last = map.last_position();
+	let entry = map.last_entry().unwrap();
+	assert_eq!(last, Some(entry.position()));
+	assert_eq!(&99, entry.key());
+	assert_eq!((&99, &1), entry.get());
+	*entry.into_mut() = 100;
+	assert_eq!(Some((&99, &100)), map.last_key_value());
+	
+	map.remove_lazy(&99);
+	assert_eq!((98, 0), map.last_entry().unwrap().remove_entry());
+	map.check_invariants();
+	assert_eq!(Some((&97, &6)), map.last_key_value());
+}
+
+#[test]
+fn test_aa_map_range()
+{
+	use std::ops::Bound::{Excluded, Included, Unbounded};
+	
+	let mut map = Map::<String, i32>::new();
+	let mut reference = std::collections::BTreeMap::<String, i32>::new();
+	for (i, word) in ["apple", "banana", "cherry", "kiwi", "lemon", "mango", "melon", "orange", "zucchini"].iter().enumerate()
+	{
+		map.insert(word.to_string(), i as i32);
+		reference.insert(word.to_string(), i as i32);
+	}
+	
+	assert!(map.range::<str, _>((Included("a"), Excluded("m"))).map(|(k, _)| k.as_str()).eq(["apple", "banana", "cherry", "kiwi", "lemon"]));
+	assert!(map.range::<str, _>((Included("kiwi"), Included("mango"))).rev().map(|(_, &v)| v).eq([5, 4, 3]));
+	assert!(map.range::<str, _>((Excluded("kiwi"), Excluded("melon"))).map(|(_, &v)| v).eq([4, 5]));
+	assert_eq!(0, map.range::<str, _>((Included("n"), Excluded("o"))).count());
+	assert_eq!(0, map.range::<str, _>((Included("zz"), Unbounded)).count());
+	assert_eq!(0, map.range::<str, _>((Included("m"), Excluded("a"))).count());
+	assert!(map.range::<str, _>((Unbounded, Unbounded)).eq(map.iter()));
+	
+	for lower in ["", "a", "apple", "c", "lemon", "m", "zucchini", "zz"]
+	{
+		for upper in ["", "b", "kiwi", "lz", "melon", "zucchini", "zz"]
+		{
+			if lower < upper
+			{
+				assert!(map.range::<str, _>((Included(lower), Excluded(upper))).eq(reference.range::<str, _>((Included(lower), Excluded(upper)))));
+				assert!(map.range::<str, _>((Excluded(lower), Included(upper))).rev().eq(reference.range::<str, _>((Excluded(lower), Included(upper))).rev()));
+			}
+		}
+	}
+	
+	map.remove_lazy("kiwi");
+	assert!(map.range::<str, _>((Included("c"), Excluded("m"))).map(|(k, _)| k.as_str()).eq(["cherry", "lemon"]));
+}
+
+#[test]
+fn test_aa_map_is_empty()
+{
+	let mut map = Map::<i32, i32>::new();
+	assert!(map.is_empty());
+	
+	map.insert_all((0 .. 10000).map(|i| (i, i)));
+	assert!(! map.is_empty());
+	
+	map.retain(|k, _| *k == 0);
+	assert!(! map.is_empty());
+	map.remove_lazy(&0);
+	assert!(map.is_empty());
+	assert!(map.iter().next().is_none());
+}
+
+#[test]
+fn test_aa_map_entry()
+{
+	let mut map = Map::<i32, String>::new();
+	
+	match map.entry(5)
+	{
+		aa::tree::Entry::Vacant(entry) =>
+		{
+			assert_eq!(5, *entry.key());
+			entry.insert(String::from("five")).push('!');
+		}
+		aa::tree::Entry::Occupied(_) => panic!(),
+	}
+	assert_eq!(Some(&String::from("five!")), map.get(&5));
+	
+	for i in 0 .. 100
+	{
+		map.entry(i).or_insert_with(|| i.to_string()).push('.');
+	}
+	map.check_invariants();
+	assert_eq!(100, map.len());
+	assert_eq!("five!.", map[&5]);
+	assert_eq!("6.", map[&6]);
+	
+	match map.entry(6)
+	{
+		aa::tree::Entry::Occupied(mut entry) =>
+		{
+			assert_eq!(6, *entry.key());
+			assert_eq!("6.", entry.get().1);
+			assert_eq!("6.", entry.insert(String::from("six")));
+			entry.get_mut().push('?');
+			assert_eq!("six?", entry.remove());
+		}
+		aa::tree::Entry::Vacant(_) => panic!(),
+	}
+	assert_eq!(None, map.get(&6));
+	assert_eq!(99, map.len());
+	
+	map.entry(7).and_modify(|v| v.clear()).or_insert(String::from("unused"));
+	map.entry(6).and_modify(|v| v.clear()).or_insert(String::from("6"));
+	assert_eq!("", map[&7]);
+	assert_eq!("6", map[&6]);
+	assert_eq!(8, *map.entry(8).key());
+	
+	map.remove_lazy(&9);
+	assert_eq!(99, map.len());
+	match map.entry(9)
+	{
+		aa::tree::Entry::Vacant(entry) => assert_eq!("9", entry.insert(String::from("9"))),
+		aa::tree::Entry::Occupied(_) => panic!(),
+	}
+	assert_eq!(100, map.len());
+	assert!(map.iter().map(|(k, _)| *k).eq(0 .. 100));
+	map.check_invariants();
+}
+
+#[test]
+fn test_aa_map_get_many()
+{
+	let mut map = Map::<String, i32>::new();
+	for (i, word) in ["one", "two", "three", "four"].into_iter().enumerate()
+	{
+		map.insert(word.to_string(), i as i32 + 1);
+	}
+	
+	assert_eq!([Some(&2), None, Some(&4), Some(&2)], map.get_many(["two", "five", "four", "two"]));
+	assert_eq!([None; 0], map.get_many::<str, 0>([]));
+	
+	map.remove_lazy("four");
+	assert_eq!([Some(&1), None], map.get_many(["one", "four"]));
+}
+
+#[test]
+fn test_aa_map_entry_position()
+{
+	let mut map = Map::<i32, String>::new();
+	let mut positions = std::collections::HashMap::<i32, usize>::new();
+	
+	for i in [5, 3, 8, 1, 4, 7, 9, 2, 6, 0]
+	{
+		let aa::tree::Entry::Vacant(entry) = map.entry(i) else {panic!()};
+		let position = entry.prospective_position();
+		entry.insert(i.to_string());
+		positions.insert(i, position);
+	}
+	
+	map.remove(&4);
+	map.remove_lazy(&6);
+	
+	for i in [4, 6, 10]
+	{
+		let aa::tree::Entry::Vacant(entry) = map.entry(i) else {panic!()};
+		let position = entry.prospective_position();
+		entry.insert(i.to_string());
+		positions.insert(i, position);
+	}
+	
+	for i in [1, 9]
+	{
+		map.insert(i * 100, String::new());
+	}
+	
+	for (key, position) in positions
+	{
+		let aa::tree::Entry::Occupied(entry) = map.entry(key) else {panic!()};
+		assert_eq!(position, entry.position());
+		assert_eq!(key.to_string(), map.impl_at(position).1);
+	}
+}
+
+#[test]
+fn test_aa_map_drain_filter()
+{
+	let mut map = Map::<i32, String>::new();
+	for i in (0 .. 20).rev()
+	{
+		map.insert(i, i.to_string());
+	}
+	
+	let drained = map.drain_filter(|k, v| {v.push('!'); k % 2 == 1}).collect::<Vec<_>>();
+	assert_eq!((1 .. 20).step_by(2).map(|i| (i, format!("{}!", i))).collect::<Vec<_>>(), drained);
+	assert!(map.iter().map(|(k, _)| *k).eq((0 .. 20).step_by(2)));
+	assert!(map.iter().all(|(k, v)| *v == format!("{}!", k)));
+	map.check_invariants();
+	
+	// Dropping early keeps the entries not visited yet
+	let mut it = map.drain_filter(|k, _| *k % 4 == 0);
+	assert_eq!(Some(0), it.next().map(|(k, _)| k));
+	assert_eq!(Some(4), it.next().map(|(k, _)| k));
+	std::mem::drop(it);
+	assert!(map.iter().map(|(k, _)| *k).eq([2, 6, 8, 10, 12, 14, 16, 18]));
+	map.check_invariants();
+	
+	map.remove_lazy(&8);
+	assert!(map.drain_filter(|_, _| true).map(|(k, _)| k).eq([2, 6, 10, 12, 14, 16, 18]));
+	assert!(map.is_empty());
+}
+
+#[test]
+fn test_aa_map_insert_replaces_key()
+{
+	/// Ordered only by the `id`, the `name` is a payload.
+	#[derive(Debug)]
+	struct Key
+	{
+		id: i32,
+		name: &'static str,
+	}
+	
+	impl PartialEq for Key
+	{
+		fn eq(&self, other: &Self) -> bool {self.id == other.id}
+	}
+	
+	impl Eq for Key {}
+	
+	impl PartialOrd for Key
+	{
+		fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {Some(self.cmp(other))}
+	}
+	
+	impl Ord for Key
+	{
+		fn cmp(&self, other: &Self) -> std::cmp::Ordering {self.id.cmp(&other.id)}
+	}
+	
+	let mut map = Map::<Key, i32>::new();
+	assert_eq!(None, map.insert(Key {id: 1, name: "old"}, 10));
+	assert_eq!(None, map.insert(Key {id: 2, name: "other"}, 20));
+	assert_eq!(Some(10), map.insert(Key {id: 1, name: "new"}, 11));
+	
+	assert_eq!(2, map.len());
+	assert!(map.iter().map(|(k, v)| (k.name, *v)).eq([("new", 11), ("other", 20)]));
+	
+	// Values filled through an entry keep the stored key
+	*map.entry(Key {id: 2, name: "ignored"}).or_insert(0) += 1;
+	assert!(map.iter().map(|(k, v)| (k.name, *v)).eq([("new", 11), ("other", 21)]));
+}
+
+#[test]
+fn test_aa_map_from_sorted_unique_iter()
+{
+	for length in [0, 1, 2, 5, 64, 1000]
+	{
+		let map = Map::from_sorted_unique_iter((0 .. length).map(|i| (i.to_string().repeat(2), i)).collect::<std::collections::BTreeMap<_, _>>());
+		map.check_invariants();
+		
+		let mut reference = Map::new();
+		for i in 0 .. length
+		{
+			reference.insert(i.to_string().repeat(2), i);
+		}
+		assert_eq!(reference, map);
+		assert_eq!(length, map.len());
+	}
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "the input is not strictly sorted")]
+fn test_aa_map_from_sorted_unique_iter_unsorted()
+{
+	Map::from_sorted_unique_iter([(1, 'a'), (3, 'b'), (2, 'c')]);
+}
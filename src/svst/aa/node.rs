@@ -4,8 +4,11 @@ pub trait Entry
 {
 	type Key;
 	type Value;
+	/// The borrowed form of [Value](Self::Value), such as `&Key` or `(&Key, &Mapped)`.
+	type ValueRef<'t> where Self: 't;
 	fn key(&self) -> &Self::Key;
 	fn value(self) -> Self::Value;
+	fn value_ref(&self) -> Self::ValueRef<'_>;
 }
 
 #[derive(Debug)]
@@ -14,6 +17,10 @@ pub struct Node<Type>
 	pub(super) parent: usize,
 	pub(super) descendants: [usize; 2],
 	pub(super) level: i16,
+	/// The number of values in the subtree which are not tombstones.
+	pub(super) size: usize,
+	/// The value is logically removed but the node is kept in the tree.
+	pub(super) tombstone: bool,
 	value: Type,
 }
 
@@ -36,6 +43,8 @@ impl<Type> Node<Type>
 			parent: usize::MAX,
 			descendants: [usize::MAX, usize::MAX],
 			level: 0,
+			size: 1,
+			tombstone: false,
 			value,
 		}
 	}
@@ -58,6 +67,123 @@ where
 	unreachable!();
 }
 
+/// Returns the number of nodes in the subtree rooted at `index`.
+pub(super) fn subtree_size<Nodes, Type>(nodes: &Nodes, index: usize) -> usize
+where
+	Nodes: ?Sized + std::ops::Index<usize, Output = Node<Type>>
+{
+	if index == usize::MAX
+	{
+		return 0;
+	}
+	
+	return nodes[index].size;
+}
+
+fn update_size<Nodes, Type>(nodes: &mut Nodes, index: usize)
+where
+	Nodes: ?Sized + std::ops::IndexMut<usize, Output = Node<Type>>
+{
+	nodes[index].size = ! nodes[index].tombstone as usize
+		+ subtree_size(nodes, nodes[index].descendants[0]) + subtree_size(nodes, nodes[index].descendants[1])
+	;
+}
+
+/// Marks or unmarks the node at `index` as a tombstone and updates the sizes of its subtree and all its ancestors.
+pub(super) fn set_tombstone<Nodes, Type>(nodes: &mut Nodes, mut index: usize, tombstone: bool)
+where
+	Nodes: ?Sized + std::ops::IndexMut<usize, Output = Node<Type>>
+{
+	if nodes[index].tombstone == tombstone
+	{
+		return;
+	}
+	
+	nodes[index].tombstone = tombstone;
+	
+	while index != usize::MAX
+	{
+		if tombstone
+		{
+			nodes[index].size -= 1;
+		}
+		else
+		{
+			nodes[index].size += 1;
+		}
+		
+		index = nodes[index].parent;
+	}
+}
+
+/// Access to the nodes through a raw pointer which reads only the link fields.
+/// No reference to a whole node is created, so the references to the values handed out earlier stay valid.
+pub(super) struct RawNodes<Type>
+{
+	nodes: std::ptr::NonNull<Node<Type>>,
+	len: usize,
+}
+
+impl<Type> RawNodes<Type>
+{
+	pub(super) fn new(nodes: &mut [Node<Type>]) -> Self
+	{
+		Self {len: nodes.len(), nodes: std::ptr::NonNull::from(nodes).cast()}
+	}
+	
+	fn node(&self, index: usize) -> *mut Node<Type>
+	{
+		debug_assert!(index < self.len);
+		return unsafe {self.nodes.as_ptr().add(index)};
+	}
+	
+	/// # Safety
+	/// The `index` must be a position of an initialized node.
+	pub(super) unsafe fn tombstone(&self, index: usize) -> bool
+	{
+		std::ptr::addr_of!((*self.node(index)).tombstone).read()
+	}
+	
+	/// # Safety
+	/// The `index` must be a position of an initialized node.
+	pub(super) unsafe fn value(&self, index: usize) -> *mut Type
+	{
+		std::ptr::addr_of_mut!((*self.node(index)).value)
+	}
+	
+	/// Returns the position following the `index` in the in-order traversal or `usize::MAX` if there is none.
+	/// # Safety
+	/// The nodes must form a valid tree containing the `index`.
+	pub(super) unsafe fn successor(&self, mut index: usize) -> usize
+	{
+		let descendants = |index: usize| std::ptr::addr_of!((*self.node(index)).descendants).read();
+		let mut next = descendants(index)[1];
+		
+		if next != usize::MAX
+		{
+			while next != usize::MAX
+			{
+				index = next;
+				next = descendants(index)[0];
+			}
+			
+			return index;
+		}
+		
+		loop
+		{
+			let parent = std::ptr::addr_of!((*self.node(index)).parent).read();
+			
+			if parent == usize::MAX || descendants(parent)[0] == index
+			{
+				return parent;
+			}
+			
+			index = parent;
+		}
+	}
+}
+
 pub struct Iterator<Nodes: ?Sized>
 {
 	#[allow(dead_code)] // Actually used by implementors
@@ -82,7 +208,12 @@ macro_rules! iter_impl
 			
 			let desc = $this.nodes[$this.bounds[$index]].descendants[1 - $index];
 			
-			if desc != usize::MAX
+			// The front and the back met at the last remaining node
+			if $this.bounds[0] == $this.bounds[1]
+			{
+				$this.bounds = [usize::MAX, usize::MAX];
+			}
+			else if desc != usize::MAX
 			{
 				$this.bounds[$index] = desc;
 				
@@ -147,6 +278,9 @@ where
 		nodes[index].descendants[0] = nodes[l_index].descendants[1];
 		nodes[l_index].descendants[1] = index;
 		
+		nodes[l_index].size = nodes[index].size;
+		update_size(nodes, index);
+		
 		return l_index;
 	}
 	
@@ -179,6 +313,9 @@ where
 		nodes[r_index].descendants[0] = index;
 		nodes[r_index].level = nodes[r_index].level + 1;
 		
+		nodes[r_index].size = nodes[index].size;
+		update_size(nodes, index);
+		
 		return r_index;
 	}
 	
@@ -275,6 +412,31 @@ where
 		nodes[successor].level = level;
 	}
 	
+	{
+		let size = nodes[index].size;
+		nodes[index].size = nodes[successor].size;
+		nodes[successor].size = size;
+	}
+	
+	// The swapped sizes count the node which was there before, recount the path between them
+	// if only one of the two nodes is a tombstone
+	if nodes[index].tombstone != nodes[successor].tombstone
+	{
+		let mut ancestor = index;
+		
+		loop
+		{
+			update_size(nodes, ancestor);
+			
+			if ancestor == successor
+			{
+				break;
+			}
+			
+			ancestor = nodes[ancestor].parent;
+		}
+	}
+	
 // 	NOTE unnecessary, this is set in `erase_rebalance`
 // 	if (successor_rdes != -1)
 // 	{
@@ -292,6 +454,16 @@ where
 	nodes[index].parent = parent;
 	nodes[parent].descendants[parent_index] = index;
 	
+	{
+		let mut ancestor = parent;
+		
+		while ancestor != usize::MAX
+		{
+			nodes[ancestor].size += 1;
+			ancestor = nodes[ancestor].parent;
+		}
+	}
+	
 	let mut changes = CHANGE_PROPAGATION_DISTANCE;
 	
 	while {index = parent; parent = nodes[parent].parent;
@@ -347,6 +519,16 @@ where
 		return nodes[index].descendants[1];
 	}
 	
+	{
+		let mut ancestor = parent;
+		
+		while ancestor != usize::MAX
+		{
+			nodes[ancestor].size -= 1;
+			ancestor = nodes[ancestor].parent;
+		}
+	}
+	
 	{
 		let parent_index = get_parent_index(nodes, index, parent);
 		nodes[parent].descendants[parent_index] = rdes;
@@ -511,5 +693,13 @@ where
 		swap_nodes(nodes, index, successor);
 	}
 	
-	return erase_rebalance_leaf(nodes, index);
+	let new_root = erase_rebalance_leaf(nodes, index);
+	
+	// The successor took the place of the erased root but the rebalancing stopped before reaching it
+	if new_root == usize::MAX && successor != usize::MAX && nodes[successor].parent == usize::MAX
+	{
+		return successor;
+	}
+	
+	return new_root;
 }
@@ -1,4 +1,5 @@
 use crate::svst::aa;
+use crate::svst::aa::node::Entry;
 
 #[derive(Debug)]
 pub struct SetEntry<KeyType>(KeyType);
@@ -7,23 +8,25 @@ impl<KeyType> aa::node::Entry for SetEntry<KeyType>
 {
 	type Key = KeyType;
 	type Value = KeyType;
+	type ValueRef<'t> = &'t KeyType where KeyType: 't;
 	fn key(&self) -> &Self::Key {&self.0}
 	fn value(self) -> Self::Value {self.0}
+	fn value_ref(&self) -> Self::ValueRef<'_> {&self.0}
 }
 
 pub type Set<KeyType> = aa::tree::Tree<SetEntry<KeyType>>;
 
 impl<KeyType> Set<KeyType>
 {
-	pub fn first(&self) -> Option<&KeyType> {self.impl_first().map(|k| &k.0)}
-	pub fn last(&self) -> Option<&KeyType> {self.impl_last().map(|k| &k.0)}
+	pub fn first(&self) -> Option<&KeyType> {self.impl_first().map(|k| k.value_ref())}
+	pub fn last(&self) -> Option<&KeyType> {self.impl_last().map(|k| k.value_ref())}
 	
 	pub fn contains<Key>(&self, key: &Key) -> bool
 	where
 		KeyType: std::borrow::Borrow<Key> + std::cmp::Ord,
 		Key: ?Sized + std::cmp::Ord,
 	{
-		aa::node::find(unsafe {self.repository.as_slice()}, self.root, key).0 != usize::MAX
+		self.find_live(key) != usize::MAX
 	}
 	
 	pub fn get<Key>(&self, key: &Key) -> Option<&KeyType>
@@ -34,6 +37,14 @@ impl<KeyType> Set<KeyType>
 		self.impl_get(key).map(|k| &k.0)
 	}
 	
+	/// Returns the `rank`-th smallest value in the set, counting from zero.
+	/// # Time complexity
+	/// _O(log n)_ where _n_ is the number of values in the set.
+	pub fn select(&self, rank: usize) -> Option<&KeyType>
+	{
+		self.impl_select(rank).map(|k| k.value_ref())
+	}
+	
 	pub fn insert(&mut self, value: KeyType) -> bool
 	where
 		KeyType: std::cmp::Ord
@@ -41,6 +52,46 @@ impl<KeyType> Set<KeyType>
 		self.try_insert(SetEntry {0: value}, |v| v.is_none())
 	}
 	
+	/// Constructs a set from the values of an iterator in any order by sorting them and building the tree bottom-up.
+	/// The resulting tree is perfectly balanced, of equal values only the first one is kept.
+	/// # Time complexity
+	/// _O(n log n)_ for sorting, the tree itself is built in _O(n)_.
+	pub fn from_unsorted<Iter>(iter: Iter) -> Self
+	where
+		KeyType: std::cmp::Ord,
+		Iter: std::iter::IntoIterator<Item = KeyType>,
+	{
+		let mut values = iter.into_iter().collect::<Vec<_>>();
+		values.sort();
+		values.dedup();
+		return Self::from_sorted_iter(values.into_iter().map(|value| SetEntry {0: value}));
+	}
+	
+	/// Constructs a perfectly balanced set from values which are already sorted and unique, without checking it in release builds.
+	/// If the values are not strictly ascending, the set is in an unspecified but memory-safe state.
+	/// # Panics
+	/// Panics in debug builds if the values are not strictly ascending.
+	/// # Time complexity
+	/// _O(n)_.
+	pub fn from_sorted_unique_iter<Iter>(iter: Iter) -> Self
+	where
+		KeyType: std::cmp::Ord,
+		Iter: std::iter::IntoIterator<Item = KeyType>,
+	{
+		Self::from_sorted_iter(iter.into_iter().map(|value| SetEntry {0: value}))
+	}
+	
+	/// Inserts all the values of the iterator and returns the number of values which were not present.
+	pub fn insert_all<Iter>(&mut self, iter: Iter) -> usize
+	where
+		KeyType: std::cmp::Ord,
+		Iter: std::iter::IntoIterator<Item = KeyType>,
+	{
+		let iter = iter.into_iter();
+		self.repository.reserve(iter.size_hint().0);
+		return iter.map(|value| self.insert(value) as usize).sum();
+	}
+	
 	pub fn replace(&mut self, value: KeyType) -> Option<KeyType>
 	where
 		KeyType: std::cmp::Ord
@@ -53,7 +104,7 @@ impl<KeyType> Set<KeyType>
 		KeyType: std::borrow::Borrow<Key> + std::cmp::Ord,
 		Key: ?Sized + std::cmp::Ord,
 	{
-		let index = aa::node::find(unsafe {self.repository.as_slice()}, self.root, value).0;
+		let index = self.find_live(value);
 		
 		if index != usize::MAX
 		{
@@ -64,6 +115,9 @@ impl<KeyType> Set<KeyType>
 		return false;
 	}
 	
+	/// Retains only the values satisfying the predicate.
+	/// Unlike [Map::retain](aa::Map::retain), the predicate receives shared references
+	/// because modifying the values could break the ordering of the set.
 	pub fn retain<Function>(&mut self, mut function: Function)
 	where
 		KeyType: std::cmp::Ord,
@@ -72,6 +126,103 @@ impl<KeyType> Set<KeyType>
 		self.impl_retain(move |k| function(&k.0));
 	}
 	
+	/// Retains only the values which are also contained in `other`.
+	/// # Time complexity
+	/// _O(n + m + k log n)_, where _n_ and _m_ are the lengths of the sets and _k_ is the number of removed values.
+	pub fn retain_in(&mut self, other: &Set<KeyType>)
+	where KeyType: std::cmp::Ord
+	{
+		self.remove_merged(other, false);
+	}
+	
+	/// Removes all the values which are contained in `other`.
+	/// # Time complexity
+	/// _O(n + m + k log n)_, where _n_ and _m_ are the lengths of the sets and _k_ is the number of removed values.
+	pub fn remove_all(&mut self, other: &Set<KeyType>)
+	where KeyType: std::cmp::Ord
+	{
+		self.remove_merged(other, true);
+	}
+	
+	/// Walks both sets in order and removes the values whose presence in `other` equals `present`.
+	fn remove_merged(&mut self, other: &Set<KeyType>, present: bool)
+	where KeyType: std::cmp::Ord
+	{
+		let mut positions = Vec::new();
+		let mut others = other.iter().peekable();
+		let mut it = aa::node::Iterator::<&[aa::node::Node<SetEntry<KeyType>>]>
+		{
+			first: self.first,
+			last: self.last,
+			bounds: [self.first, self.last],
+			nodes: unsafe {self.repository.as_slice()},
+		};
+		
+		loop
+		{
+			let position = aa::node::iter_impl!(it, 0);
+			
+			if position == usize::MAX
+			{
+				break;
+			}
+			
+			if it.nodes[position].tombstone
+			{
+				continue;
+			}
+			
+			let key = &it.nodes[position].as_ref().0;
+			
+			while others.next_if(|other| *other < key).is_some() {}
+			
+			if (others.peek() == Some(&key)) == present
+			{
+				positions.push(position);
+			}
+		}
+		
+		for position in positions
+		{
+			self.remove_at(position);
+		}
+	}
+	
+	/// Returns an iterator which removes the values satisfying the predicate and yields them in ascending order.
+	/// The values for which the predicate returns `false` are kept.
+	/// Dropping the iterator early leaves the values not yet visited in the set.
+	pub fn iter_remove_if<'t, Function>(&'t mut self, mut function: Function) -> impl std::iter::Iterator<Item = KeyType> + 't
+	where Function: std::ops::FnMut(&KeyType) -> bool + 't
+	{
+		let mut current = self.first;
+		
+		std::iter::from_fn(move || loop
+		{
+			if current == usize::MAX
+			{
+				return None;
+			}
+			
+			let position = current;
+			
+			// Resolve the successor first, the removal does not move the remaining nodes
+			let mut it = aa::node::Iterator::<&[aa::node::Node<SetEntry<KeyType>>]>
+			{
+				first: self.first,
+				last: self.last,
+				bounds: [position, self.last],
+				nodes: unsafe {self.repository.as_slice()},
+			};
+			aa::node::iter_impl!(it, 0);
+			current = it.bounds[0];
+			
+			if ! self.repository[position].tombstone && function(&self.repository[position].as_ref().0)
+			{
+				return self.remove_at(position);
+			}
+		})
+	}
+	
 	pub fn iter<'t>(&'t self) -> aa::node::Iterator<&'t [aa::node::Node<SetEntry<KeyType>>]>
 	{
 		aa::node::Iterator::<&'t [aa::node::Node<SetEntry<KeyType>>]>
@@ -84,16 +235,29 @@ impl<KeyType> Set<KeyType>
 	}
 }
 
+impl<'t, KeyType> aa::tree::OccupiedEntry<'t, SetEntry<KeyType>>
+{
+	/// Removes the value from the set and returns it.
+	pub fn remove(self) -> KeyType
+	{
+		self.remove_entry()
+	}
+}
+
 impl<'t, Type> std::iter::Iterator for aa::node::Iterator<&'t [aa::node::Node<SetEntry<Type>>]>
 {
 	type Item = &'t Type;
 	
 	fn next(&mut self) -> Option<Self::Item>
 	{
-		match aa::node::iter_impl!(self, 0)
+		loop
 		{
-			usize::MAX => None,
-			i => Some(&self.nodes[i].as_ref().0),
+			match aa::node::iter_impl!(self, 0)
+			{
+				usize::MAX => return None,
+				i if self.nodes[i].tombstone => continue,
+				i => return Some(self.nodes[i].as_ref().value_ref()),
+			}
 		}
 	}
 }
@@ -102,14 +266,75 @@ impl<'t, Type> std::iter::DoubleEndedIterator for aa::node::Iterator<&'t [aa::no
 {
 	fn next_back(&mut self) -> Option<Self::Item>
 	{
-		match aa::node::iter_impl!(self, 1)
+		loop
+		{
+			match aa::node::iter_impl!(self, 1)
+			{
+				usize::MAX => return None,
+				i if self.nodes[i].tombstone => continue,
+				i => return Some(self.nodes[i].as_ref().value_ref()),
+			}
+		}
+	}
+}
+
+impl<'t, Type> std::iter::FusedIterator for aa::node::Iterator<&'t [aa::node::Node<SetEntry<Type>>]>
+{
+}
+
+impl<KeyType> PartialEq for Set<KeyType>
+where
+	KeyType: PartialEq,
+{
+	fn eq(&self, other: &Self) -> bool
+	{
+		self.len() == other.len() && self.iter().eq(other.iter())
+	}
+}
+
+impl<KeyType> Eq for Set<KeyType>
+where
+	KeyType: Eq,
+{
+}
+
+/// The values are hashed in ascending order, equal sets have equal hashes.
+impl<KeyType> std::hash::Hash for Set<KeyType>
+where
+	KeyType: std::hash::Hash,
+{
+	fn hash<Hasher: std::hash::Hasher>(&self, state: &mut Hasher)
+	{
+		state.write_usize(self.len());
+		
+		for value in self.iter()
 		{
-			usize::MAX => None,
-			i => Some(&self.nodes[i].as_ref().0),
+			value.hash(state);
 		}
 	}
 }
 
+/// Sets are compared lexicographically by their values in ascending order, the same way as [std::collections::BTreeSet].
+impl<KeyType> PartialOrd for Set<KeyType>
+where
+	KeyType: PartialOrd,
+{
+	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering>
+	{
+		self.iter().partial_cmp(other.iter())
+	}
+}
+
+impl<KeyType> Ord for Set<KeyType>
+where
+	KeyType: Ord,
+{
+	fn cmp(&self, other: &Self) -> std::cmp::Ordering
+	{
+		self.iter().cmp(other.iter())
+	}
+}
+
 #[test]
 fn test_aa_set_0()
 {
@@ -197,6 +422,515 @@ fn test_aa_set_retain()
 	assert_eq!(None, it.next());
 }
 
+#[test]
+fn test_aa_set_invariants()
+{
+	use rand::{Rng, SeedableRng};
+	let mut rng = rand::rngs::SmallRng::seed_from_u64(0);
+	let mut set = Set::<i32>::new();
+	set.check_invariants();
+	
+	for _ in 0 .. 5_000
+	{
+		let value = rng.gen_range(0 .. 500);
+		
+		if rng.gen_bool(0.6)
+		{
+			set.insert(value);
+		}
+		else
+		{
+			set.remove(&value);
+		}
+		
+		set.check_invariants();
+	}
+	
+	while ! set.is_empty()
+	{
+		set.pop_first();
+		set.check_invariants();
+	}
+}
+
+#[test]
+fn test_aa_set_remove_root()
+{
+	// The successor of the root is swapped into its place and the rebalancing stops below it
+	let mut set = Set::from_sorted_unique_iter(0 .. 46);
+	assert_eq!(&22, set.repository[set.root].as_ref().key());
+	let successor = set.position_of(&23).unwrap();
+	
+	assert!(set.remove(&22));
+	assert_eq!(successor, set.root);
+	assert_eq!(usize::MAX, set.repository[set.root].parent);
+	set.check_invariants();
+	assert!((0 .. 46).filter(|&i| i != 22).eq(set.iter().copied()));
+}
+
+#[test]
+fn test_aa_set_differential()
+{
+	use rand::{Rng, SeedableRng};
+	let mut rng = rand::rngs::SmallRng::seed_from_u64(1);
+	let mut set = Set::<i32>::new();
+	let mut reference = std::collections::BTreeSet::<i32>::new();
+	
+	// Small key ranges make removals of inner nodes with a direct right successor frequent,
+	// larger ones produce deeper successor searches
+	for range in [16, 128, 1024]
+	{
+		for _ in 0 .. 10_000
+		{
+			let value = rng.gen_range(0 .. range);
+			
+			if rng.gen_bool(0.5)
+			{
+				assert_eq!(reference.insert(value), set.insert(value));
+			}
+			else
+			{
+				assert_eq!(reference.remove(&value), set.remove(&value));
+			}
+			
+			set.check_invariants();
+			assert_eq!(reference.len(), set.len());
+			assert_eq!(reference.first(), set.first());
+			assert_eq!(reference.last(), set.last());
+			assert_eq!(reference.contains(&value), set.contains(&value));
+		}
+		
+		assert!(reference.iter().eq(set.iter()));
+		assert!(reference.iter().rev().eq(set.iter().rev()));
+	}
+}
+
+#[test]
+fn test_aa_set_pop_first()
+{
+	use rand::seq::SliceRandom;
+	let mut values = (0 .. 1000).collect::<Vec<i32>>();
+	values.shuffle(&mut rand::thread_rng());
+	let mut set = Set::<i32>::new();
+	for &v in values.iter()
+	{
+		set.insert(v);
+	}
+	
+	for i in 0 .. 1000
+	{
+		assert_eq!(Some(&i), set.first());
+		assert_eq!(Some(i), set.pop_first());
+		assert_eq!(if i == 999 {None} else {Some(&999)}, set.last());
+		set.check_invariants();
+	}
+	
+	assert_eq!(None, set.pop_first());
+}
+
+#[test]
+fn test_aa_set_value_ref()
+{
+	let entry = SetEntry {0: String::from("a")};
+	assert_eq!("a", entry.value_ref());
+	assert!(std::ptr::eq(entry.key(), entry.value_ref()));
+	assert_eq!("a", entry.value());
+	
+	let mut set = Set::<i32>::new();
+	for v in [2, 1, 3]
+	{
+		set.insert(v);
+	}
+	assert_eq!(Some(&1), set.first());
+	assert_eq!(Some(&3), set.last());
+	assert_eq!(Some(&2), set.select(1));
+}
+
+#[test]
+fn test_aa_set_iter_fused()
+{
+	let mut set = Set::<i32>::new();
+	for v in [3, 1, 2, 5, 4]
+	{
+		set.insert(v);
+	}
+	
+	let mut it = set.iter();
+	assert_eq!(Some(&1), it.next());
+	assert_eq!(Some(&5), it.next_back());
+	assert_eq!(Some(&2), it.next());
+	assert_eq!(Some(&4), it.next_back());
+	assert_eq!(Some(&3), it.next());
+	
+	for _ in 0 .. 3
+	{
+		assert_eq!(None, it.next());
+		assert_eq!(None, it.next_back());
+	}
+	
+	let mut it = set.iter().fuse();
+	assert_eq!(5, it.by_ref().count());
+	assert_eq!(None, it.next());
+	
+	for n in 0 ..= 5
+	{
+		let mut it = set.iter();
+		let front = it.by_ref().take(n).copied().collect::<Vec<_>>();
+		let back = it.rev().copied().collect::<Vec<_>>();
+		assert_eq!(5, front.len() + back.len());
+		assert!(front.into_iter().chain(back.into_iter().rev()).eq(1 ..= 5));
+	}
+}
+
+#[test]
+fn test_aa_set_height()
+{
+	let mut set = Set::<i32>::new();
+	assert_eq!(0, set.height());
+	assert_eq!(-1, set.max_level());
+	
+	set.insert(0);
+	assert_eq!(1, set.height());
+	assert_eq!(0, set.max_level());
+	
+	for k in 2 .. 16
+	{
+		set.clear();
+		let n = (1 << k) - 1;
+		
+		for v in 0 .. n
+		{
+			set.insert(v);
+		}
+		
+		let height = set.height();
+		assert!(k as usize <= height);
+		assert!(height as f64 <= 1.44 * ((n + 2) as f64).log2());
+		assert!(set.max_level() as usize <= height);
+	}
+}
+
+#[test]
+fn test_aa_set_first_last_position()
+{
+	let mut set = Set::<i32>::new();
+	assert_eq!(None, set.first_position());
+	assert_eq!(None, set.last_position());
+	
+	for v in [5, 3, 8, 1, 9, 4]
+	{
+		set.insert(v);
+	}
+	
+	let mut expected = vec![1, 3, 4, 5, 8, 9];
+	
+	while let Some(position) = set.first_position()
+	{
+		assert_eq!(&expected[0], unsafe {set.impl_get_at_unchecked(position)}.value_ref());
+		assert_eq!(expected.last(), set.last_position().map(|p| set.impl_at(p).value_ref()));
+		assert_eq!(Some(expected.remove(0)), set.remove_at(position));
+		set.check_invariants();
+	}
+	
+	assert!(expected.is_empty());
+	assert_eq!(None, set.last_position());
+}
+
+#[test]
+fn test_aa_set_contains_all_sorted()
+{
+	use rand::{Rng, SeedableRng};
+	let mut rng = rand::rngs::SmallRng::seed_from_u64(0);
+	let mut set = Set::<i32>::new();
+	
+	for _ in 0 .. 500
+	{
+		set.insert(rng.gen_range(0 .. 1000));
+	}
+	
+	let mut queries = (0 .. 300).map(|_| rng.gen_range(-10 .. 1010)).collect::<Vec<_>>();
+	queries.sort();
+	
+	let expected = queries.iter().map(|q| set.contains(q)).collect::<Vec<_>>();
+	assert_eq!(expected, set.contains_all_sorted(queries.iter().copied()).collect::<Vec<_>>());
+	assert_eq!(0, Set::<i32>::new().contains_all_sorted(queries).filter(|&b| b).count());
+	assert_eq!(0, set.contains_all_sorted(std::iter::empty()).count());
+}
+
+#[test]
+fn test_aa_set_drain()
+{
+	let mut set = Set::<i32>::new();
+	for v in (0 .. 1000).rev()
+	{
+		set.insert(v);
+	}
+	
+	let capacity = set.capacity();
+	assert!((0 .. 1000).eq(set.drain()));
+	assert!(set.is_empty());
+	
+	for v in 0 .. 1000
+	{
+		set.insert(v);
+	}
+	
+	{
+		let mut drain = set.drain();
+		assert_eq!(1000, drain.len());
+		assert!((0 .. 500).eq(drain.by_ref().take(500)));
+		assert_eq!(500, drain.len());
+	}
+	
+	assert!(set.is_empty());
+	assert_eq!(None, set.first());
+	assert_eq!(capacity, set.capacity());
+	set.check_invariants();
+	
+	set.insert(7);
+	assert_eq!(Some(&7), set.first());
+	assert_eq!(Some(&7), set.last());
+}
+
+#[test]
+fn test_aa_set_rebuild()
+{
+	use rand::seq::SliceRandom;
+	let mut values = (0 .. 1000).collect::<Vec<i32>>();
+	values.shuffle(&mut rand::thread_rng());
+	let mut set = Set::<i32>::new();
+	for &v in values.iter()
+	{
+		set.insert(v);
+	}
+	
+	for &v in values[100 ..].iter()
+	{
+		set.remove(&v);
+	}
+	
+	let mut expected = values[.. 100].to_vec();
+	expected.sort();
+	
+	set.rebuild();
+	set.check_invariants();
+	assert!(expected.iter().eq(set.iter()));
+	assert!(expected.iter().enumerate().all(|(i, v)| set.position_of(v) == Some(i)));
+	assert_eq!(Set::from_sorted_unique_iter(expected.iter().copied()).height(), set.height());
+	
+	for v in expected.iter().step_by(3)
+	{
+		set.remove_lazy(v);
+	}
+	
+	expected = expected.iter().copied().enumerate().filter(|(i, _)| i % 3 != 0).map(|(_, v)| v).collect();
+	
+	set.rebuild();
+	set.check_invariants();
+	assert!(expected.iter().eq(set.iter()));
+	assert!(expected.iter().enumerate().all(|(i, v)| set.position_of(v) == Some(i)));
+	
+	set.shrink_to_fit();
+	assert_eq!(expected.len(), set.capacity());
+	assert!(expected.iter().eq(set.iter()));
+	assert!(expected.iter().all(|v| set.contains(v)));
+	
+	set.insert(-1);
+	set.check_invariants();
+	assert_eq!(Some(&-1), set.first());
+}
+
+#[test]
+fn test_aa_set_pop_last()
+{
+	use rand::seq::SliceRandom;
+	let mut values = (0 .. 1000).collect::<Vec<i32>>();
+	values.shuffle(&mut rand::thread_rng());
+	let mut set = Set::<i32>::new();
+	for &v in values.iter()
+	{
+		set.insert(v);
+	}
+	
+	for i in (0 .. 1000).rev()
+	{
+		assert_eq!(Some(&i), set.last());
+		assert_eq!(Some(i), set.pop_last());
+		assert_eq!(if i == 0 {None} else {Some(&0)}, set.first());
+		set.check_invariants();
+	}
+	
+	assert_eq!(None, set.pop_last());
+}
+
+#[test]
+fn test_aa_set_rank_select()
+{
+	use rand::{Rng, SeedableRng};
+	let mut rng = rand::rngs::SmallRng::seed_from_u64(0);
+	let mut set = Set::<i32>::new();
+	let mut reference = std::collections::BTreeSet::<i32>::new();
+	
+	assert_eq!(0, set.rank(&0));
+	assert_eq!(None, set.select(0));
+	assert_eq!(0, set.range_count(..));
+	
+	for _ in 0 .. 3_000
+	{
+		let value = rng.gen_range(0 .. 300);
+		
+		if rng.gen_bool(0.6)
+		{
+			set.insert(value);
+			reference.insert(value);
+		}
+		else
+		{
+			set.remove(&value);
+			reference.remove(&value);
+		}
+	}
+	
+	set.check_invariants();
+	
+	for (i, value) in reference.iter().enumerate()
+	{
+		assert_eq!(i, set.rank(value));
+		assert_eq!(Some(value), set.select(i));
+	}
+	assert_eq!(None, set.select(reference.len()));
+	
+	for a in (-10 .. 310).step_by(7)
+	{
+		for b in (-10 .. 310).step_by(11)
+		{
+			assert_eq!(reference.iter().filter(|&&v| a <= v && v < b).count(), set.range_count(a .. b));
+			assert_eq!(reference.iter().filter(|&&v| a <= v && v <= b).count(), set.range_count(a ..= b));
+			let bounds = (std::ops::Bound::Excluded(a), std::ops::Bound::Included(b));
+			assert_eq!(reference.iter().filter(|&&v| a < v && v <= b).count(), set.range_count(bounds));
+		}
+		assert_eq!(reference.range(a ..).count(), set.range_count(a ..));
+		assert_eq!(reference.range(.. a).count(), set.range_count(.. a));
+	}
+}
+
+#[test]
+fn test_aa_set_ord()
+{
+	let set = |values: &[i32]|
+	{
+		let mut result = Set::<i32>::new();
+		for &v in values
+		{
+			result.insert(v);
+		}
+		result
+	};
+	
+	assert!(set(&[1, 2]) < set(&[1, 3]));
+	assert!(set(&[1, 2]) < set(&[1, 2, 3]));
+	assert!(set(&[]) < set(&[0]));
+	assert!(set(&[2]) > set(&[1, 2, 3]));
+	assert_eq!(set(&[3, 1, 2]), set(&[1, 2, 3]));
+	assert_eq!(std::cmp::Ordering::Equal, set(&[2, 1]).cmp(&set(&[1, 2])));
+	assert_ne!(set(&[1, 2]), set(&[1, 2, 3]));
+	
+	let mut sets = std::collections::BTreeSet::new();
+	sets.insert(set(&[1, 3]));
+	sets.insert(set(&[1, 2, 3]));
+	sets.insert(set(&[1, 2]));
+	sets.insert(set(&[2, 1]));
+	assert_eq!(3, sets.len());
+	assert_eq!(Some(&set(&[1, 2])), sets.first());
+	assert_eq!(Some(&set(&[1, 3])), sets.last());
+}
+
+#[test]
+fn test_aa_set_hash()
+{
+	use std::hash::{Hash, Hasher};
+	let hash = |set: &Set<i32>|
+	{
+		let mut hasher = std::collections::hash_map::DefaultHasher::new();
+		set.hash(&mut hasher);
+		hasher.finish()
+	};
+	
+	let mut a = Set::<i32>::new();
+	let mut b = Set::<i32>::new();
+	
+	for i in 0 .. 100
+	{
+		a.insert(i);
+		b.insert(99 - i);
+	}
+	
+	assert_eq!(hash(&a), hash(&b));
+	
+	let mut sets = std::collections::HashSet::new();
+	sets.insert(a);
+	assert!(sets.contains(&b));
+}
+
+#[test]
+fn test_aa_set_from_parts()
+{
+	let mut set = Set::<i32>::new();
+	for i in 0 .. 100
+	{
+		set.insert((i * 37) % 100);
+	}
+	for i in (0 .. 100).step_by(3)
+	{
+		set.remove(&i);
+	}
+	
+	let expected = set.iter().copied().collect::<Vec<_>>();
+	let (repository, root, first, last) = set.into_parts();
+	
+	let set = Set::<i32>::from_parts_checked(repository, root, first, last).unwrap();
+	assert!(expected.iter().eq(set.iter()));
+	for i in 0 .. 100
+	{
+		assert_eq!(i % 3 != 0, set.contains(&i));
+	}
+	
+	let (repository, root, first, last) = set.into_parts();
+	assert!(Set::<i32>::from_parts_checked(repository, root, last, first).is_err());
+	
+	let mut set = Set::<i32>::new();
+	set.insert(1);
+	set.insert(2);
+	let (repository, _, first, last) = set.into_parts();
+	assert!(Set::<i32>::from_parts_checked(repository, usize::MAX, first, last).is_err());
+}
+
+#[test]
+fn test_aa_set_from_parts_deep()
+{
+	// A left spine of decreasing levels with a leaf hanging off the right of each node,
+	// the position of each node is equal to its key
+	let depth = 20_000;
+	let mut repository = crate::svst::Repository::new();
+	repository.insert(aa::node::Node::new(SetEntry {0: 0}));
+	
+	for i in 1 .. depth + 1
+	{
+		let spine = repository.insert(aa::node::Node::new(SetEntry {0: 2 * i - 1}));
+		let leaf = repository.insert(aa::node::Node::new(SetEntry {0: 2 * i}));
+		let left = if i == 1 {0} else {spine - 2};
+		
+		repository[spine].descendants = [left, leaf];
+		repository[spine].level = i as i16;
+		repository[spine].size = 2 * i as usize + 1;
+		repository[left].parent = spine;
+		repository[leaf].parent = spine;
+	}
+	
+	let set = Set::<i32>::from_parts_checked(repository, 2 * depth as usize - 1, 0, 2 * depth as usize).unwrap();
+	assert_eq!(2 * depth as usize + 1, set.len());
+}
+
 /*
 #[test]
 fn test_to_dot()
@@ -219,3 +953,550 @@ fn test_to_dot()
 	}
 }
 */
+
+#[test]
+fn test_aa_set_remove_lazy()
+{
+	use rand::seq::SliceRandom;
+	let mut values = (0 .. 1000).collect::<Vec<i32>>();
+	values.shuffle(&mut rand::thread_rng());
+	let mut set = Set::<i32>::new();
+	for &v in values.iter()
+	{
+		set.insert(v);
+	}
+	
+	for &v in values[500 ..].iter()
+	{
+		assert!(set.remove_lazy(&v));
+		assert!(! set.remove_lazy(&v));
+	}
+	
+	let mut expected = values[.. 500].to_vec();
+	expected.sort();
+	
+	set.check_invariants();
+	assert_eq!(500, set.len());
+	assert!(expected.iter().eq(set.iter()));
+	assert!(expected.iter().rev().eq(set.iter().rev()));
+	assert!(values[.. 500].iter().all(|v| set.contains(v)));
+	assert!(values[500 ..].iter().all(|v| ! set.contains(v)));
+	assert_eq!(expected.first(), set.first());
+	assert_eq!(expected.last(), set.last());
+	for (i, v) in expected.iter().enumerate()
+	{
+		assert_eq!(i, set.rank(v));
+		assert_eq!(Some(v), set.select(i));
+	}
+	
+	set.compact_tombstones();
+	set.check_invariants();
+	assert_eq!(500, set.len());
+	assert!(expected.iter().eq(set.iter()));
+	set.shrink_to_fit();
+	assert_eq!(500, set.capacity());
+	
+	set.remove_lazy(&expected[0]);
+	assert!(set.insert(expected[0]));
+	set.check_invariants();
+	assert_eq!(500, set.len());
+	assert!(expected.iter().eq(set.iter()));
+}
+
+#[test]
+fn test_aa_set_remove_range()
+{
+	let mut set = Set::<i32>::new();
+	for v in 0 .. 100
+	{
+		set.insert(v);
+	}
+	
+	assert_eq!(50, set.remove_range(25 .. 75));
+	set.check_invariants();
+	assert!((0 .. 25).chain(75 .. 100).eq(set.iter().copied()));
+	assert_eq!(0, set.remove_range(25 .. 75));
+	
+	assert_eq!(10, set.remove_range(.. 10));
+	assert_eq!(5, set.remove_range(95 ..));
+	set.check_invariants();
+	assert_eq!(Some(&10), set.first());
+	assert_eq!(Some(&94), set.last());
+	
+	set.remove_lazy(&20);
+	assert_eq!(4, set.remove_range(18 ..= 22));
+	set.check_invariants();
+	assert!((10 .. 18).chain(23 .. 25).chain(75 .. 95).eq(set.iter().copied()));
+	
+	assert_eq!(30, set.remove_range::<i32, _>(..));
+	set.check_invariants();
+	assert!(set.is_empty());
+	assert_eq!(None, set.first());
+}
+
+#[test]
+fn test_aa_set_to_dot_sorted()
+{
+	fn render<Type: std::fmt::Debug>(set: &Set<Type>) -> String
+	{
+		let mut result = Vec::new();
+		set.to_dot_sorted(&mut result).unwrap();
+		return String::from_utf8(result).unwrap();
+	}
+	
+	let mut lhs = Set::<i32>::new();
+	let mut rhs = Set::<i32>::new();
+	for key in [1, 2, 3]
+	{
+		lhs.insert(key);
+	}
+	for key in [2, 1, 3]
+	{
+		rhs.insert(key);
+	}
+	
+	assert_ne!(lhs.position_of(&1), rhs.position_of(&1));
+	assert_eq!(render(&lhs), render(&rhs));
+	assert_eq!(concat!
+	(
+		"digraph tree {\n",
+		"\"1\" [shape=record];\n",
+		"\"1\" -> \"2\" [style=dotted];\n",
+		"\"2\" [shape=record];\n",
+		"\"2\" -> \"1\";\n",
+		"\"2\" -> \"3\";\n",
+		"\"3\" [shape=record];\n",
+		"\"3\" -> \"2\" [style=dotted];\n",
+		"}\n",
+	), render(&lhs));
+	
+	use rand::seq::SliceRandom;
+	let mut values = (0 .. 1000).map(|v| v.to_string()).collect::<Vec<_>>();
+	let mut lhs = Set::<String>::new();
+	let mut rhs = Set::<String>::new();
+	for v in values.iter()
+	{
+		lhs.insert(v.clone());
+	}
+	values.shuffle(&mut rand::thread_rng());
+	for v in values.iter()
+	{
+		rhs.insert(v.clone());
+	}
+	
+	lhs.rebuild();
+	rhs.rebuild();
+	assert_eq!(render(&lhs), render(&rhs));
+}
+
+#[test]
+fn test_aa_set_clear_refill_capacity()
+{
+	use rand::{Rng, SeedableRng};
+	let mut rng = rand::rngs::SmallRng::seed_from_u64(2416);
+	let mut set = Set::<u32>::new();
+	let mut capacity = None;
+	
+	for _ in 0 .. 100
+	{
+		set.clear();
+		
+		while set.len() < 1000
+		{
+			set.insert(rng.gen());
+		}
+		
+		set.check_invariants();
+		assert_eq!(*capacity.get_or_insert(set.capacity()), set.capacity());
+	}
+}
+
+#[test]
+fn test_aa_set_insert_all()
+{
+	let mut set = Set::<i32>::new();
+	assert_eq!(3, set.insert_all([1, 2, 2, 3, 1]));
+	assert_eq!(2, set.insert_all((0 .. 5).rev()));
+	assert_eq!(0, set.insert_all(std::iter::empty()));
+	set.check_invariants();
+	assert!(set.iter().copied().eq(0 .. 5));
+}
+
+#[test]
+fn test_aa_set_iter_remove_if()
+{
+	let mut set = Set::<i32>::new();
+	set.insert_all(0 .. 100);
+	
+	assert!(set.iter_remove_if(|&v| v < 50).eq(0 .. 50));
+	set.check_invariants();
+	assert!(set.iter().copied().eq(50 .. 100));
+	
+	set.remove_lazy(&60);
+	assert!(set.iter_remove_if(|&v| v % 10 == 0).eq([50, 70, 80, 90]));
+	set.check_invariants();
+	assert!(set.iter().copied().eq((51 .. 100).filter(|v| v % 10 != 0)));
+	
+	let mut it = set.iter_remove_if(|&v| v > 95);
+	assert_eq!(Some(96), it.next());
+	std::mem::drop(it);
+	set.check_invariants();
+	assert_eq!(Some(&99), set.last());
+	
+	assert_eq!(set.len(), set.iter_remove_if(|_| true).count());
+	set.check_invariants();
+	assert!(set.is_empty());
+}
+
+#[test]
+fn test_aa_set_first_last_entry()
+{
+	let mut set = Set::<i32>::new();
+	set.insert_all(0 .. 10);
+	
+	let entry = set.first_entry().unwrap();
+	assert_eq!(&0, entry.get());
+	assert_eq!(0, entry.remove());
+	assert_eq!(9, set.last_entry().unwrap().remove());
+	set.check_invariants();
+	assert!(set.iter().copied().eq(1 .. 9));
+}
+
+#[test]
+fn test_aa_set_from_unsorted()
+{
+	use rand::{Rng, SeedableRng};
+	let mut rng = rand::rngs::SmallRng::seed_from_u64(2429);
+	
+	for length in (0 .. 300).chain([1000, 1023, 1024, 1025, 10000])
+	{
+		let values = (0 .. length).map(|_| rng.gen_range(0 .. 2 * length + 1)).collect::<Vec<_>>();
+		let set = Set::from_unsorted(values.iter().copied());
+		set.check_invariants();
+		assert!(set.iter().eq(values.iter().collect::<std::collections::BTreeSet<_>>()));
+		assert!(set.height() <= (set.len() + 1).next_power_of_two().ilog2() as usize);
+		
+		let mut set = set;
+		for v in values.iter()
+		{
+			set.remove(v);
+			set.insert(*v + 1);
+		}
+		set.check_invariants();
+	}
+}
+
+#[test]
+fn test_aa_set_cursor()
+{
+	use std::ops::Bound;
+	let mut set = Set::<i32>::new();
+	set.insert_all((0 .. 20).map(|v| v * 2));
+	
+	let mut cursor = set.lower_bound(Bound::Excluded(&5));
+	assert_eq!(Some(&6), cursor.peek_next());
+	assert_eq!(Some(&4), cursor.peek_prev());
+	assert_eq!(Some(&6), cursor.move_next());
+	assert_eq!(Some(&8), cursor.move_next());
+	assert_eq!(Some(&8), cursor.move_prev());
+	assert_eq!(Some(&6), cursor.peek_prev());
+	
+	assert_eq!(Some(&6), set.lower_bound(Bound::Included(&6)).peek_next());
+	assert_eq!(Some(&8), set.lower_bound(Bound::Excluded(&6)).peek_next());
+	assert_eq!(Some(&6), set.upper_bound(Bound::Included(&6)).peek_prev());
+	assert_eq!(Some(&4), set.upper_bound(Bound::Excluded(&6)).peek_prev());
+	assert_eq!(Some(&6), set.upper_bound(Bound::Excluded(&7)).peek_prev());
+	assert_eq!(Some(&8), set.upper_bound(Bound::Excluded(&7)).peek_next());
+	
+	let mut cursor = set.lower_bound::<i32>(Bound::Unbounded);
+	assert_eq!(None, cursor.peek_prev());
+	assert!(std::iter::from_fn(|| cursor.move_next()).eq(set.iter()));
+	assert_eq!(None, cursor.peek_next());
+	assert_eq!(Some(&38), cursor.move_prev());
+	
+	let mut cursor = set.upper_bound::<i32>(Bound::Unbounded);
+	assert_eq!(None, cursor.peek_next());
+	assert!(std::iter::from_fn(|| cursor.move_prev()).eq(set.iter().rev()));
+	assert_eq!(None, set.lower_bound(Bound::Excluded(&38)).peek_next());
+	assert_eq!(Some(&38), set.lower_bound(Bound::Excluded(&38)).peek_prev());
+	
+	set.remove_lazy(&6);
+	set.remove_lazy(&8);
+	let mut cursor = set.lower_bound(Bound::Excluded(&5));
+	assert_eq!(Some(&4), cursor.peek_prev());
+	assert_eq!(Some(&10), cursor.move_next());
+	assert_eq!(Some(&10), cursor.move_prev());
+	assert_eq!(Some(&4), cursor.move_prev());
+	assert_eq!(Some(&4), set.upper_bound(Bound::Included(&8)).peek_prev());
+	
+	let empty = Set::<i32>::new();
+	assert_eq!(None, empty.lower_bound(Bound::Included(&0)).peek_next());
+	assert_eq!(None, empty.upper_bound(Bound::Included(&0)).peek_prev());
+}
+
+#[test]
+fn test_aa_set_clear_and_shrink_to()
+{
+	let mut set = Set::<i32>::new();
+	for i in 0 .. 10000
+	{
+		set.insert(i);
+	}
+	assert!(set.capacity() >= 10000);
+	
+	set.clear_and_shrink_to(100);
+	assert!(set.is_empty());
+	assert!(set.capacity() <= 100);
+	
+	for i in (0 .. 50).rev()
+	{
+		set.insert(i);
+	}
+	assert_eq!(50, set.len());
+	assert!(set.iter().copied().eq(0 .. 50));
+	set.check_invariants();
+	
+	set.clear_and_shrink_to(1000);
+	assert!(set.capacity() <= 100);
+}
+
+#[test]
+fn test_aa_set_retain_in_remove_all()
+{
+	use rand::{Rng, SeedableRng};
+	let mut rng = rand::rngs::SmallRng::seed_from_u64(4);
+	
+	for _ in 0 .. 50
+	{
+		let mut set = Set::<i32>::new();
+		let mut other = Set::<i32>::new();
+		for _ in 0 .. rng.gen_range(0 .. 200)
+		{
+			set.insert(rng.gen_range(0 .. 300));
+		}
+		for _ in 0 .. rng.gen_range(0 .. 200)
+		{
+			other.insert(rng.gen_range(0 .. 300));
+		}
+		
+		let reference = set.iter().copied().collect::<std::collections::BTreeSet<_>>();
+		let other_reference = other.iter().copied().collect::<std::collections::BTreeSet<_>>();
+		
+		let mut intersection = Set::<i32>::new();
+		intersection.insert_all(reference.iter().copied());
+		intersection.retain_in(&other);
+		intersection.check_invariants();
+		assert!(intersection.iter().eq(reference.intersection(&other_reference)));
+		
+		set.remove_all(&other);
+		set.check_invariants();
+		assert!(set.iter().eq(reference.difference(&other_reference)));
+		assert!(other.iter().eq(other_reference.iter()));
+	}
+	
+	let mut set = Set::<i32>::new();
+	set.insert_all(0 .. 10);
+	let mut other = Set::<i32>::new();
+	other.insert_all([3, 5, 7, 11]);
+	set.remove_lazy(&5);
+	
+	let mut copy = Set::<i32>::new();
+	copy.insert_all(0 .. 10);
+	copy.retain_in(&other);
+	assert!(copy.iter().copied().eq([3, 5, 7]));
+	
+	set.retain_in(&other);
+	assert!(set.iter().copied().eq([3, 7]));
+	set.remove_all(&other);
+	assert!(set.is_empty());
+}
+
+#[test]
+fn test_aa_set_is_empty()
+{
+	let mut set = Set::<i32>::new();
+	assert!(set.is_empty());
+	
+	set.insert_all(0 .. 10000);
+	assert!(! set.is_empty());
+	
+	// Tombstones are not counted as values
+	for i in 0 .. 10000
+	{
+		set.remove_lazy(&i);
+		assert_eq!(i == 9999, set.is_empty());
+	}
+	assert!(set.insert(5));
+	assert!(! set.is_empty());
+	
+	set.compact_tombstones();
+	assert_eq!(1, set.len());
+	set.remove(&5);
+	assert!(set.is_empty());
+	
+	set.insert_all(0 .. 100);
+	set.clear();
+	assert!(set.is_empty());
+}
+
+#[test]
+fn test_aa_set_fragmentation()
+{
+	let mut set = Set::<i32>::new();
+	assert_eq!(0.0, set.fragmentation());
+	
+	set.insert_all(0 .. 1000);
+	let capacity = set.capacity();
+	assert_eq!(1.0 - 1000.0 / capacity as f64, set.fragmentation());
+	
+	for i in 0 .. 900
+	{
+		set.remove(&i);
+	}
+	assert_eq!(capacity, set.capacity());
+	assert!((set.fragmentation() - (1.0 - 100.0 / capacity as f64)).abs() < 1e-9);
+	assert!(set.fragmentation() > 0.89);
+	
+	set.shrink_to_fit();
+	set.rebuild();
+	set.shrink_to_fit();
+	assert!(set.fragmentation() < 0.1);
+}
+
+#[test]
+fn test_aa_set_next_prev_position()
+{
+	use rand::{Rng, SeedableRng};
+	let mut rng = rand::rngs::SmallRng::seed_from_u64(7);
+	
+	let mut set = Set::<i32>::new();
+	assert_eq!(None, set.next_position(0));
+	
+	for _ in 0 .. 2000
+	{
+		set.insert(rng.gen_range(0 .. 5000));
+	}
+	for _ in 0 .. 500
+	{
+		set.remove(&rng.gen_range(0 .. 5000));
+		set.remove_lazy(&rng.gen_range(0 .. 5000));
+	}
+	
+	let mut forward = Vec::new();
+	let mut position = set.first_position();
+	while let Some(current) = position
+	{
+		forward.push(set.impl_at(current).0);
+		position = set.next_position(current);
+	}
+	assert!(forward.iter().eq(set.iter()));
+	
+	let mut backward = Vec::new();
+	let mut position = set.last_position();
+	while let Some(current) = position
+	{
+		backward.push(set.impl_at(current).0);
+		position = set.prev_position(current);
+	}
+	assert!(backward.iter().eq(set.iter().rev()));
+	
+	assert_eq!(None, set.next_position(set.capacity()));
+	let free = set.repository.first_free_index().unwrap();
+	assert_eq!(None, set.prev_position(free));
+}
+
+#[test]
+fn test_aa_set_from_sorted_unique_iter()
+{
+	for length in [0, 1, 2, 3, 7, 8, 100, 1000]
+	{
+		let set = Set::from_sorted_unique_iter((0 .. length).map(|i| i * 3));
+		set.check_invariants();
+		
+		let mut reference = Set::new();
+		reference.insert_all((0 .. length).map(|i| i * 3));
+		assert_eq!(reference, set);
+	}
+	
+	let set = Set::from_sorted_unique_iter((0 .. 10).filter(|i| i % 2 == 0));
+	assert!(set.iter().copied().eq([0, 2, 4, 6, 8]));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "the input is not strictly sorted")]
+fn test_aa_set_from_sorted_unique_iter_duplicate()
+{
+	Set::from_sorted_unique_iter([1, 2, 2, 3]);
+}
+
+#[test]
+fn test_aa_set_remove_lazy_mixed()
+{
+	use rand::{Rng, SeedableRng};
+	let mut rng = rand::rngs::SmallRng::seed_from_u64(11);
+	
+	let mut set = Set::from_sorted_unique_iter(0 .. 15);
+	set.remove_lazy(&8);
+	set.remove(&7);
+	set.check_invariants();
+	assert_eq!(9, set.rank(&11));
+	assert_eq!(Some(&14), set.select(12));
+	
+	for _ in 0 .. 20
+	{
+		let mut set = Set::<i32>::new();
+		let mut reference = std::collections::BTreeSet::<i32>::new();
+		
+		for _ in 0 .. 2000
+		{
+			let key = rng.gen_range(0 .. 300);
+			
+			match rng.gen_range(0 .. 12)
+			{
+				0 ..= 3 => assert_eq!(reference.insert(key), set.insert(key)),
+				4 ..= 6 => assert_eq!(reference.remove(&key), set.remove_lazy(&key)),
+				7 ..= 8 => assert_eq!(reference.remove(&key), set.remove(&key)),
+				9 => assert_eq!(reference.pop_first(), set.pop_first()),
+				10 => assert_eq!(reference.pop_last(), set.pop_last()),
+				_ =>
+				{
+					let end = key + rng.gen_range(0 .. 20);
+					let removed = reference.range(key .. end).count();
+					reference.retain(|k| ! (key .. end).contains(k));
+					assert_eq!(removed, set.remove_range(key .. end));
+				}
+			}
+			
+			set.check_invariants();
+			assert_eq!(reference.len(), set.len());
+			
+			let probe = rng.gen_range(0 .. 300);
+			assert_eq!(reference.range(.. probe).count(), set.rank(&probe));
+			let rank = rng.gen_range(0 .. reference.len() + 1);
+			assert_eq!(reference.iter().nth(rank), set.select(rank));
+		}
+		
+		assert!(set.iter().eq(reference.iter()));
+	}
+}
+
+#[test]
+fn test_aa_set_position_tombstone()
+{
+	let mut set = Set::<i32>::new();
+	set.insert_all(0 .. 10);
+	let position = set.position_of(&4).unwrap();
+	assert_eq!(4, set.impl_at(position).0);
+	
+	set.remove_lazy(&4);
+	assert!(set.impl_get_at(position).is_none());
+	assert!(set.impl_get_at_mut(position).is_none());
+	assert!(std::panic::catch_unwind(|| set.impl_at(position).0).is_err());
+	
+	assert_eq!(None, set.remove_at(position));
+	assert_eq!(9, set.len());
+	assert_eq!(None, set.remove_at(position));
+	set.check_invariants();
+	assert!(set.iter().copied().eq((0 .. 10).filter(|i| *i != 4)));
+}
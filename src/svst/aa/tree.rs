@@ -7,6 +7,8 @@ pub struct Tree<Type>
 	pub(super) root: usize,
 	pub(super) first: usize,
 	pub(super) last: usize,
+	/// The number of nodes removed by [remove_lazy](Self::remove_lazy) which are still present.
+	pub(super) tombstones: usize,
 	pub(super) repository: Repository<node::Node<Type>>,
 }
 
@@ -19,25 +21,192 @@ impl<Type> Tree<Type>
 			root: usize::MAX,
 			first: usize::MAX,
 			last: usize::MAX,
+			tombstones: 0,
 			repository: Repository::new(),
 		}
 	}
 	
+	/// Constructs a new, empty tree whose storage can hold exactly `capacity` values without reallocating.
+	pub fn with_repository_capacity(capacity: usize) -> Self
+	{
+		let mut result = Self::new();
+		result.repository = Repository::with_capacity(capacity);
+		return result;
+	}
+	
+	/// Constructs a perfectly balanced tree from values sorted by strictly ascending keys in _O(n)_.
+	/// The nodes occupy consecutive positions in the order of the values.
+	/// Panics in debug builds if the keys are not strictly ascending.
+	pub(super) fn from_sorted_iter(values: impl std::iter::IntoIterator<Item = Type>) -> Self
+	where
+		Type: node::Entry,
+		Type::Key: std::cmp::Ord,
+	{
+		let values = values.into_iter();
+		let mut result = Self::with_repository_capacity(values.size_hint().0);
+		
+		for value in values
+		{
+			let position = result.repository.insert(node::Node::new(value));
+			debug_assert!(position == 0 || result.repository[position - 1].as_ref().key() < result.repository[position].as_ref().key(),
+				"the input is not strictly sorted"
+			);
+		}
+		
+		result.link_consecutive();
+		return result;
+	}
+	
+	/// Links all the nodes, which occupy consecutive positions from zero in the order of the values,
+	/// into a perfectly balanced tree.
+	fn link_consecutive(&mut self)
+	{
+		let length = self.repository.len();
+		
+		if length != 0
+		{
+			self.root = Self::link_sorted(unsafe {self.repository.as_mut_slice()}, 0, length, usize::MAX);
+			self.first = 0;
+			self.last = length - 1;
+		}
+	}
+	
+	/// Links the nodes at positions `[begin, end)` into a subtree of the `parent` and returns its root.
+	/// The left subtree is never larger than the right one, so the levels given by the sizes satisfy the AA-tree invariants.
+	fn link_sorted(nodes: &mut [node::Node<Type>], begin: usize, end: usize, parent: usize) -> usize
+	{
+		if begin == end
+		{
+			return usize::MAX;
+		}
+		
+		let size = end - begin;
+		let middle = begin + (size - 1) / 2;
+		nodes[middle].parent = parent;
+		nodes[middle].descendants =
+		[
+			Self::link_sorted(nodes, begin, middle, middle),
+			Self::link_sorted(nodes, middle + 1, end, middle),
+		];
+		nodes[middle].size = size;
+		nodes[middle].level = ((size + 1).ilog2() - 1) as i16;
+		
+		return middle;
+	}
+	
+	/// Constructs a new, empty tree reusing the allocation of the `repository`.
+	/// Any nodes remaining in the `repository` are dropped.
+	pub fn from_repository_parts(mut repository: Repository<node::Node<Type>>) -> Self
+	{
+		repository.clear();
+		let mut result = Self::new();
+		result.repository = repository;
+		return result;
+	}
+	
+	/// Consumes the tree and returns its node storage, so that its allocation can be reused by
+	/// [from_repository_parts](Self::from_repository_parts).
+	pub fn into_repository(self) -> Repository<node::Node<Type>>
+	{
+		self.repository
+	}
+	
+	/// Constructs a tree from its parts as returned by [into_parts](Self::into_parts).
+	/// 
+	/// # Safety
+	/// The parts must form a valid AA-tree, see [from_parts_checked](Self::from_parts_checked).
+	pub unsafe fn from_parts(repository: Repository<node::Node<Type>>, root: usize, first: usize, last: usize) -> Self
+	{
+		let tombstones = repository.iter().filter(|node| node.tombstone).count();
+		Self {root, first, last, tombstones, repository}
+	}
+	
+	/// Decomposes the tree into its node storage and the positions of the root, the first and the last node.
+	pub fn into_parts(self) -> (Repository<node::Node<Type>>, usize, usize, usize)
+	{
+		(self.repository, self.root, self.first, self.last)
+	}
+	
 	/// Returns the total number of values the collection can hold without reallocating.
 	pub fn capacity(&self) -> usize {self.repository.capacity()}
 	
+	/// Returns the fraction of the capacity of the node storage which holds no node, between `0.0` and `1.0`.
+	/// Nodes removed by [remove_lazy](Self::remove_lazy) still occupy their positions and do not count as holes.
+	/// A high value after many removals indicates that [shrink_to_fit](Self::shrink_to_fit) or [rebuild](Self::rebuild) could release memory.
+	pub fn fragmentation(&self) -> f64
+	{
+		if self.capacity() == 0
+		{
+			return 0.0;
+		}
+		
+		return 1.0 - self.repository.len() as f64 / self.capacity() as f64;
+	}
+	
 	/// Returns the number of elements in the collection.
-	pub fn len(&self) -> usize {self.repository.len()}
+	pub fn len(&self) -> usize {self.repository.len() - self.tombstones}
 	
 	/// Returns `true` if the collection contains no values.
 	pub fn is_empty(&self) -> bool {self.len() == 0}
 	
+	/// Returns the number of nodes on the longest path from the root to a leaf.
+	/// # Time complexity
+	/// _O(n)_ where _n_ is the number of values in the collection.
+	pub fn height(&self) -> usize
+	{
+		let mut result = 0;
+		let mut stack = Vec::new();
+		
+		if self.root != usize::MAX
+		{
+			stack.push((self.root, 1));
+		}
+		
+		while let Some((index, depth)) = stack.pop()
+		{
+			result = std::cmp::max(result, depth);
+			
+			for descendant in self.repository[index].descendants
+			{
+				if descendant != usize::MAX
+				{
+					stack.push((descendant, depth + 1));
+				}
+			}
+		}
+		
+		return result;
+	}
+	
+	/// Returns the AA level of the root, the leaves have level 0.
+	/// Returns -1 if the collection is empty.
+	/// # Time complexity
+	/// _O(1)_
+	pub fn max_level(&self) -> i16
+	{
+		if self.root == usize::MAX
+		{
+			return -1;
+		}
+		
+		return self.repository[self.root].level;
+	}
+	
 	pub fn clear(&mut self)
 	{
 		self.repository.clear();
 		self.root = usize::MAX;
 		self.first = usize::MAX;
 		self.last = usize::MAX;
+		self.tombstones = 0;
+	}
+	
+	/// Clears the collection and shrinks the capacity of the underlying storage to at most `max_capacity`.
+	/// Unlike [Self::clear], this bounds the memory retained after a temporary growth.
+	pub fn clear_and_shrink_to(&mut self, max_capacity: usize)
+	{
+		self.clear();
+		self.repository.shrink_to(max_capacity);
 	}
 	
 	pub(super) fn try_insert<Consumer, ResultType>(&mut self, value: Type, consumer: Consumer) -> ResultType
@@ -46,24 +215,160 @@ impl<Type> Tree<Type>
 		Type::Key: std::cmp::Ord,
 		Consumer: std::ops::FnOnce(Option<Type>) -> ResultType
 	{
-		if self.is_empty()
+		return consumer(self.insert_below(self.root, value).1);
+	}
+	
+	/// Inserts a value whose key is not less than the key at `hint`.
+	/// The search for the position starts at the lowest ancestor of `hint` whose subtree covers the key
+	/// rather than at the root, which makes inserting ascending sequences cheaper.
+	/// Returns the position of the inserted value.
+	pub(super) fn insert_after(&mut self, hint: usize, value: Type) -> usize
+	where
+		Type: node::Entry,
+		Type::Key: std::cmp::Ord,
+	{
+		let values = unsafe {self.repository.as_slice()};
+		let mut subtree_root = hint;
+		
+		while subtree_root != usize::MAX
 		{
-			self.root = self.repository.insert(node::Node::new(value));
-			self.first = self.root;
-			self.last = self.root;
-			return consumer(None);
+			let parent = values[subtree_root].parent;
+			
+			if parent == usize::MAX
+			{
+				break;
+			}
+			
+			// The keys of a left subtree are bounded by the parent's key
+			if values[parent].descendants[0] == subtree_root && value.key() < values[parent].as_ref().key()
+			{
+				break;
+			}
+			
+			subtree_root = parent;
+		}
+		
+		if subtree_root == usize::MAX
+		{
+			subtree_root = self.root;
+		}
+		
+		return self.insert_below(subtree_root, value).0;
+	}
+	
+	/// Inserts a value searching for its position only within the subtree of `subtree_root`.
+	/// Returns the position of the value and the replaced value if there was one.
+	fn insert_below(&mut self, subtree_root: usize, value: Type) -> (usize, Option<Type>)
+	where
+		Type: node::Entry,
+		Type::Key: std::cmp::Ord,
+	{
+		if self.root == usize::MAX
+		{
+			return (self.insert_new(usize::MAX, 0, value), None);
 		}
 		
-		let mut values = unsafe {self.repository.as_mut_slice()};
-		let (mut position, parent, parent_index) = node::find(values, self.root, value.key());
+		let values = unsafe {self.repository.as_mut_slice()};
+		let (position, parent, parent_index) = node::find(values, subtree_root, value.key());
 		
 		if position != usize::MAX
 		{
-			return consumer(Some(std::mem::replace(&mut values[position].as_mut(), value)));
+			let replaced = std::mem::replace(values[position].as_mut(), value);
+			
+			if values[position].tombstone
+			{
+				node::set_tombstone(values, position, false);
+				self.tombstones -= 1;
+				return (position, None);
+			}
+			
+			return (position, Some(replaced));
+		}
+		
+		return (self.insert_new(parent, parent_index, value), None);
+	}
+	
+	/// Returns the position of the value with the `key`.
+	/// If there is no such value, inserts the value returned by `function` called with the `key`, which must keep the `key`.
+	pub(super) fn find_or_insert_with<Function>(&mut self, key: Type::Key, function: Function) -> usize
+	where
+		Type: node::Entry,
+		Type::Key: std::cmp::Ord,
+		Function: std::ops::FnOnce(Type::Key) -> Type,
+	{
+		if self.root == usize::MAX
+		{
+			return self.insert_new(usize::MAX, 0, function(key));
+		}
+		
+		let values = unsafe {self.repository.as_mut_slice()};
+		let (position, parent, parent_index) = node::find(values, self.root, &key);
+		
+		if position == usize::MAX
+		{
+			return self.insert_new(parent, parent_index, function(key));
+		}
+		
+		if values[position].tombstone
+		{
+			self.revive(position, function(key));
+		}
+		
+		return position;
+	}
+	
+	/// Stores the `value` in the tombstone at the `position`, dropping the value removed earlier.
+	fn revive(&mut self, position: usize, value: Type)
+	{
+		let values = unsafe {self.repository.as_mut_slice()};
+		*values[position].as_mut() = value;
+		node::set_tombstone(values, position, false);
+		self.tombstones -= 1;
+	}
+	
+	/// Returns the entry of the `key` for in-place manipulation.
+	/// The entry remembers the located position, its methods do not search the tree again.
+	/// # Time complexity
+	/// _O(log n)_ where _n_ is the number of values in the collection.
+	pub fn entry(&mut self, key: Type::Key) -> Entry<'_, Type>
+	where
+		Type: node::Entry,
+		Type::Key: std::cmp::Ord,
+	{
+		if self.root == usize::MAX
+		{
+			return Entry::Vacant(VacantEntry {tree: self, key, placement: Placement::Below {parent: usize::MAX, parent_index: 0}});
+		}
+		
+		let (position, parent, parent_index) = node::find(unsafe {self.repository.as_slice()}, self.root, &key);
+		
+		if position == usize::MAX
+		{
+			return Entry::Vacant(VacantEntry {tree: self, key, placement: Placement::Below {parent, parent_index}});
+		}
+		
+		if self.repository[position].tombstone
+		{
+			return Entry::Vacant(VacantEntry {tree: self, key, placement: Placement::Revive {position}});
+		}
+		
+		return Entry::Occupied(OccupiedEntry {tree: self, position});
+	}
+	
+	/// Inserts a new node with the `value` as the descendant `parent_index` of the `parent`, as returned by [node::find],
+	/// or as the root if the tree is empty.
+	fn insert_new(&mut self, parent: usize, parent_index: usize, value: Type) -> usize
+	{
+		if self.root == usize::MAX
+		{
+			self.root = self.repository.insert(node::Node::new(value));
+			self.first = self.root;
+			self.last = self.root;
+			return self.root;
 		}
 		
-		position = self.repository.insert(node::Node::new(value));
-		values = unsafe {self.repository.as_mut_slice()};
+		let position = self.repository.insert(node::Node::new(value));
+		let values = unsafe {self.repository.as_mut_slice()};
 		
 		if node::insert_rebalance(values, parent, parent_index, position)
 		{
@@ -82,39 +387,235 @@ impl<Type> Tree<Type>
 			self.last = position;
 		}
 		
-		return consumer(None);
+		return position;
+	}
+	
+	/// Returns the position of the node holding the smallest value, usable with the `*_at` accessors and [remove_at](Self::remove_at).
+	pub fn first_position(&self) -> Option<usize>
+	{
+		match self.live_extreme(0)
+		{
+			usize::MAX => None,
+			position => Some(position),
+		}
+	}
+	
+	/// Returns the position of the node holding the largest value, usable with the `*_at` accessors and [remove_at](Self::remove_at).
+	pub fn last_position(&self) -> Option<usize>
+	{
+		match self.live_extreme(1)
+		{
+			usize::MAX => None,
+			position => Some(position),
+		}
+	}
+	
+	/// Returns the position of the node holding the smallest value greater than the value at `position`,
+	/// or [None] if there is none or if no node is stored at `position`.
+	/// # Time complexity
+	/// _O(log n)_ where _n_ is the number of values in the collection, amortized _O(1)_ when walking the whole collection.
+	pub fn next_position(&self, position: usize) -> Option<usize>
+	{
+		self.repository.get(position)?;
+		
+		match self.live_neighbor(position, 0)
+		{
+			usize::MAX => None,
+			position => Some(position),
+		}
+	}
+	
+	/// Returns the position of the node holding the largest value less than the value at `position`,
+	/// or [None] if there is none or if no node is stored at `position`.
+	/// # Time complexity
+	/// _O(log n)_ where _n_ is the number of values in the collection, amortized _O(1)_ when walking the whole collection.
+	pub fn prev_position(&self, position: usize) -> Option<usize>
+	{
+		self.repository.get(position)?;
+		
+		match self.live_neighbor(position, 1)
+		{
+			usize::MAX => None,
+			position => Some(position),
+		}
+	}
+	
+	/// Returns the position of the smallest (for `direction` 0) or the largest (for `direction` 1) value
+	/// which is not a tombstone or `usize::MAX` if there is none.
+	fn live_extreme(&self, direction: usize) -> usize
+	{
+		let mut it = node::Iterator::<&[node::Node<Type>]>
+		{
+			first: self.first,
+			last: self.last,
+			bounds: [self.first, self.last],
+			nodes: unsafe {self.repository.as_slice()},
+		};
+		
+		loop
+		{
+			let position = node::iter_impl!(it, direction);
+			
+			if position == usize::MAX || ! it.nodes[position].tombstone
+			{
+				return position;
+			}
+		}
+	}
+	
+	/// Returns the position of the node with the `key` which is not a tombstone or `usize::MAX` if there is none.
+	pub(super) fn find_live<Key>(&self, key: &Key) -> usize
+	where
+		Type: node::Entry,
+		Type::Key: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: ?Sized + std::cmp::Ord,
+	{
+		let values = unsafe {self.repository.as_slice()};
+		let position = node::find(values, self.root, key).0;
+		
+		if position != usize::MAX && values[position].tombstone
+		{
+			return usize::MAX;
+		}
+		
+		return position;
+	}
+	
+	/// Marks the value with the `key` as removed without rebalancing the tree, returns `true` if the value was present.
+	/// The node and its value stay in the tree until [compact_tombstones](Self::compact_tombstones)
+	/// or until a value with an equal key is inserted, they are skipped by lookups and iteration.
+	/// # Time complexity
+	/// _O(log n)_ where _n_ is the number of nodes in the tree.
+	pub fn remove_lazy<Key>(&mut self, key: &Key) -> bool
+	where
+		Type: node::Entry,
+		Type::Key: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: ?Sized + std::cmp::Ord,
+	{
+		let position = self.find_live(key);
+		
+		if position == usize::MAX
+		{
+			return false;
+		}
+		
+		node::set_tombstone(unsafe {self.repository.as_mut_slice()}, position, true);
+		self.tombstones += 1;
+		return true;
+	}
+	
+	/// Drops the values removed by [remove_lazy](Self::remove_lazy) and rebuilds the tree from the remaining values,
+	/// see [rebuild](Self::rebuild).
+	pub fn compact_tombstones(&mut self)
+	where
+		Type: node::Entry,
+		Type::Key: std::cmp::Ord,
+	{
+		if self.tombstones != 0
+		{
+			self.rebuild();
+		}
+	}
+	
+	/// Returns for each of the ascending `queries` whether the collection contains it.
+	/// The queries are merged with the in-order traversal of the collection instead of searching for each one from the root.
+	/// # Time complexity
+	/// _O(n + q)_ where _n_ is the number of values in the collection and _q_ is the number of queries.
+	pub fn contains_all_sorted<'t, Key, Iter>(&'t self, queries: Iter) -> impl std::iter::Iterator<Item = bool> + 't
+	where
+		Type: node::Entry,
+		Type::Key: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: std::cmp::Ord + 't,
+		Iter: std::iter::IntoIterator<Item = Key>,
+		Iter::IntoIter: 't,
+	{
+		use std::borrow::Borrow;
+		
+		let nodes = unsafe {self.repository.as_slice()};
+		let mut it = node::Iterator::<&[node::Node<Type>]>
+		{
+			first: self.first,
+			last: self.last,
+			bounds: [self.first, self.last],
+			nodes,
+		};
+		let mut current = node::iter_impl!(it, 0);
+		let mut previous = None;
+		
+		queries.into_iter().map(move |query|
+		{
+			if let Some(previous) = &previous
+			{
+				debug_assert!(*previous <= query, "the queries are not sorted");
+			}
+			
+			while current != usize::MAX && (nodes[current].tombstone || nodes[current].as_ref().key().borrow() < &query)
+			{
+				current = node::iter_impl!(it, 0);
+			}
+			
+			let result = current != usize::MAX && nodes[current].as_ref().key().borrow() == &query;
+			previous = Some(query);
+			return result;
+		})
+	}
+	
+	/// Returns the position of the node holding the value with the `key`.
+	/// The position stays valid until that value is removed.
+	pub fn position_of<Key>(&self, key: &Key) -> Option<usize>
+	where
+		Type: node::Entry,
+		Type::Key: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: ?Sized + std::cmp::Ord,
+	{
+		match self.find_live(key)
+		{
+			usize::MAX => None,
+			position => Some(position),
+		}
 	}
 	
+	/// Returns the value at the `position` or [None] if there is no value, including the values removed by [remove_lazy](Self::remove_lazy).
 	pub fn impl_get_at(&self, position: usize) -> Option<&Type>
 	{
-		self.repository.get(position).map(AsRef::as_ref)
+		self.repository.get(position).filter(|node| ! node.tombstone).map(AsRef::as_ref)
 	}
 	
+	/// Returns the value at the `position` or [None] if there is no value, including the values removed by [remove_lazy](Self::remove_lazy).
 	pub fn impl_get_at_mut(&mut self, position: usize) -> Option<&mut Type>
 	{
-		self.repository.get_mut(position).map(AsMut::as_mut)
+		self.repository.get_mut(position).filter(|node| ! node.tombstone).map(AsMut::as_mut)
 	}
 	
+	/// The `position` must hold a value which has not been removed by [remove_lazy](Self::remove_lazy).
 	pub unsafe fn impl_get_at_unchecked(&self, position: usize) -> &Type
 	{
 		self.repository.get_unchecked(position).as_ref()
 	}
 	
+	/// The `position` must hold a value which has not been removed by [remove_lazy](Self::remove_lazy).
 	pub unsafe fn impl_get_at_unchecked_mut(&mut self, position: usize) -> &mut Type
 	{
 		self.repository.get_unchecked_mut(position).as_mut()
 	}
 	
+	/// # Panics
+	/// Panics if there is no value at the `position`, including a value removed by [remove_lazy](Self::remove_lazy).
 	pub fn impl_at(&self, position: usize) -> &Type
 	{
-		self.repository[position].as_ref()
+		self.impl_get_at(position).expect("no value at the position")
 	}
 	
+	/// # Panics
+	/// Panics if there is no value at the `position`, including a value removed by [remove_lazy](Self::remove_lazy).
 	pub fn impl_at_mut(&mut self, position: usize) -> &mut Type
 	{
-		self.repository[position].as_mut()
+		self.impl_get_at_mut(position).expect("no value at the position")
 	}
 	
+	/// Removes the value at the `position` and returns it, or returns [None] if there is no value.
+	/// If the `position` holds a value removed by [remove_lazy](Self::remove_lazy), its node is unlinked
+	/// from the tree and the value is dropped, the result is [None] as the value has already been removed.
 	pub fn remove_at(&mut self, position: usize) -> Option<Type::Value>
 	where Type: node::Entry
 	{
@@ -123,6 +624,14 @@ impl<Type> Tree<Type>
 			return None;
 		};
 		let values = unsafe {self.repository.as_mut_slice()};
+		let tombstone = values[position].tombstone;
+		
+		if tombstone
+		{
+			node::set_tombstone(values, position, false);
+			self.tombstones -= 1;
+		}
+		
 		let parent = values[position].parent;
 		let rdes = values[position].descendants[1];
 		let new_root = node::erase_rebalance(values, position);
@@ -131,11 +640,13 @@ impl<Type> Tree<Type>
 		{
 			self.root = new_root;
 		}
-		else if self.is_empty()
+		else if self.repository.is_empty()
 		{
 			self.root = usize::MAX;
 		}
 		
+		// The minimum and the maximum have no left descendant and are therefore always leaves of level 0,
+		// they are never swapped with a successor and their captured `parent` and `rdes` stay valid
 		if position == self.first
 		{
 			if rdes != usize::MAX
@@ -153,16 +664,23 @@ impl<Type> Tree<Type>
 			self.last = parent;
 		}
 		
+		if tombstone
+		{
+			return None;
+		}
+		
 		return Some(result.value().value());
 	}
 	
 	pub(super) fn impl_retain(&mut self, mut function: impl std::ops::FnMut(&mut Type) -> bool)
 	where Type: node::Entry
 	{
+		// Removal only unlinks the node at `i` and never moves values between positions,
+		// so the positions ahead of the iterator are each visited exactly once
 		let mut it = crate::svst::bit_indexing::TransientIndexSliceIterator::new(self.repository.index_header_leaf());
 		while let Some(i) = it.next(self.repository.index_header_leaf())
 		{
-			if ! function(self.impl_at_mut(i))
+			if ! self.repository[i].tombstone && ! function(self.impl_at_mut(i))
 			{
 				self.remove_at(i);
 			}
@@ -175,7 +693,7 @@ impl<Type> Tree<Type>
 		Type::Key: std::borrow::Borrow<Key> + std::cmp::Ord,
 		Key: ?Sized + std::cmp::Ord,
 	{
-		let index = node::find(unsafe {self.repository.as_slice()}, self.root, key).0;
+		let index = self.find_live(key);
 		
 		if index != usize::MAX
 		{
@@ -187,52 +705,404 @@ impl<Type> Tree<Type>
 	
 	pub(super) fn impl_first(&self) -> Option<&Type>
 	{
-		if self.first != usize::MAX
-		{
-			Some(unsafe {self.impl_get_at_unchecked(self.first)})
-		}
-		else
-		{
-			None
-		}
+		self.first_position().map(|position| unsafe {self.impl_get_at_unchecked(position)})
 	}
 	
 	pub(super) fn impl_last(&self) -> Option<&Type>
 	{
-		if self.last != usize::MAX
-		{
-			Some(unsafe {self.impl_get_at_unchecked(self.last)})
-		}
-		else
-		{
-			None
-		}
+		self.last_position().map(|position| unsafe {self.impl_get_at_unchecked(position)})
 	}
 	
-	pub fn pop_first(&mut self) -> Option<Type::Value>
-	where Type: node::Entry
+	/// Returns the number of keys in the collection which are strictly less than `key`.
+	/// # Time complexity
+	/// _O(log n)_ where _n_ is the number of values in the collection.
+	pub fn rank<Key>(&self, key: &Key) -> usize
+	where
+		Type: node::Entry,
+		Type::Key: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: ?Sized + std::cmp::Ord,
 	{
-		if self.first != usize::MAX
-		{
-			self.remove_at(self.first)
-		}
-		else
-		{
-			None
-		}
+		self.count_less(key, false)
+	}
+	
+	/// Returns the number of keys in the collection which are contained in `range`.
+	/// # Time complexity
+	/// _O(log n)_ where _n_ is the number of values in the collection.
+	pub fn range_count<Key, Range>(&self, range: Range) -> usize
+	where
+		Type: node::Entry,
+		Type::Key: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: ?Sized + std::cmp::Ord,
+		Range: std::ops::RangeBounds<Key>,
+	{
+		let begin = match range.start_bound()
+		{
+			std::ops::Bound::Included(key) => self.count_less(key, false),
+			std::ops::Bound::Excluded(key) => self.count_less(key, true),
+			std::ops::Bound::Unbounded => 0,
+		};
+		let end = match range.end_bound()
+		{
+			std::ops::Bound::Included(key) => self.count_less(key, true),
+			std::ops::Bound::Excluded(key) => self.count_less(key, false),
+			std::ops::Bound::Unbounded => self.len(),
+		};
+		
+		return end.saturating_sub(begin);
+	}
+	
+	/// Returns the position of the node with the smallest key satisfying the lower `bound` or `usize::MAX` if there is none.
+	pub(super) fn lower_bound_position<Key>(&self, bound: std::ops::Bound<&Key>) -> usize
+	where
+		Type: node::Entry,
+		Type::Key: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: ?Sized + std::cmp::Ord,
+	{
+		use std::borrow::Borrow;
+		
+		let values = unsafe {self.repository.as_slice()};
+		let mut result = usize::MAX;
+		let mut index = self.root;
+		
+		while index != usize::MAX
+		{
+			let key = values[index].as_ref().key().borrow();
+			let satisfies = match bound
+			{
+				std::ops::Bound::Included(bound) => key >= bound,
+				std::ops::Bound::Excluded(bound) => key > bound,
+				std::ops::Bound::Unbounded => true,
+			};
+			
+			if satisfies
+			{
+				result = index;
+				index = values[index].descendants[0];
+			}
+			else
+			{
+				index = values[index].descendants[1];
+			}
+		}
+		
+		return result;
+	}
+	
+	/// Returns the position of the node with the largest key satisfying the upper `bound` or `usize::MAX` if there is none.
+	pub(super) fn upper_bound_position<Key>(&self, bound: std::ops::Bound<&Key>) -> usize
+	where
+		Type: node::Entry,
+		Type::Key: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: ?Sized + std::cmp::Ord,
+	{
+		use std::borrow::Borrow;
+		
+		let values = unsafe {self.repository.as_slice()};
+		let mut result = usize::MAX;
+		let mut index = self.root;
+		
+		while index != usize::MAX
+		{
+			let key = values[index].as_ref().key().borrow();
+			let satisfies = match bound
+			{
+				std::ops::Bound::Included(bound) => key <= bound,
+				std::ops::Bound::Excluded(bound) => key < bound,
+				std::ops::Bound::Unbounded => true,
+			};
+			
+			if satisfies
+			{
+				result = index;
+				index = values[index].descendants[1];
+			}
+			else
+			{
+				index = values[index].descendants[0];
+			}
+		}
+		
+		return result;
+	}
+	
+	/// Returns the position of the closest node which is not a tombstone in the `direction`,
+	/// 0 for the successor and 1 for the predecessor of the node at `position`, or `usize::MAX` if there is none.
+	pub(super) fn live_neighbor(&self, position: usize, direction: usize) -> usize
+	{
+		let mut it = node::Iterator::<&[node::Node<Type>]>
+		{
+			first: self.first,
+			last: self.last,
+			bounds: [self.first, self.last],
+			nodes: unsafe {self.repository.as_slice()},
+		};
+		it.bounds[direction] = position;
+		node::iter_impl!(it, direction);
+		
+		while it.bounds[direction] != usize::MAX && it.nodes[it.bounds[direction]].tombstone
+		{
+			node::iter_impl!(it, direction);
+		}
+		
+		return it.bounds[direction];
+	}
+	
+	/// Returns a double-ended iterator over the values with keys contained in `range` in ascending order.
+	/// The bounds may be of any type the keys can be borrowed as, such as `&str` for [String] keys.
+	/// # Time complexity
+	/// _O(log n)_ to find the bounds, where _n_ is the number of values in the collection.
+	pub fn range<Key, Range>(&self, range: Range) -> node::Iterator<&[node::Node<Type>]>
+	where
+		Type: node::Entry,
+		Type::Key: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: ?Sized + std::cmp::Ord,
+		Range: std::ops::RangeBounds<Key>,
+	{
+		let mut bounds = [self.lower_bound_position(range.start_bound()), self.upper_bound_position(range.end_bound())];
+		
+		if bounds.contains(&usize::MAX) || self.repository[bounds[0]].as_ref().key() > self.repository[bounds[1]].as_ref().key()
+		{
+			bounds = [usize::MAX, usize::MAX];
+		}
+		
+		return node::Iterator
+		{
+			first: self.first,
+			last: self.last,
+			bounds,
+			nodes: unsafe {self.repository.as_slice()},
+		};
+	}
+	
+	/// Returns a cursor pointing to the gap before the smallest value whose key satisfies the lower `bound`.
+	/// # Time complexity
+	/// _O(log n)_ where _n_ is the number of values in the collection.
+	pub fn lower_bound<Key>(&self, bound: std::ops::Bound<&Key>) -> Cursor<'_, Type>
+	where
+		Type: node::Entry,
+		Type::Key: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: ?Sized + std::cmp::Ord,
+	{
+		let mut next = self.lower_bound_position(bound);
+		
+		if next != usize::MAX && self.repository[next].tombstone
+		{
+			next = self.live_neighbor(next, 0);
+		}
+		
+		let prev = match next
+		{
+			usize::MAX => self.last_position().unwrap_or(usize::MAX),
+			next => self.live_neighbor(next, 1),
+		};
+		
+		return Cursor {tree: self, prev, next};
+	}
+	
+	/// Returns a cursor pointing to the gap after the largest value whose key satisfies the upper `bound`.
+	/// # Time complexity
+	/// _O(log n)_ where _n_ is the number of values in the collection.
+	pub fn upper_bound<Key>(&self, bound: std::ops::Bound<&Key>) -> Cursor<'_, Type>
+	where
+		Type: node::Entry,
+		Type::Key: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: ?Sized + std::cmp::Ord,
+	{
+		let mut prev = self.upper_bound_position(bound);
+		
+		if prev != usize::MAX && self.repository[prev].tombstone
+		{
+			prev = self.live_neighbor(prev, 1);
+		}
+		
+		let next = match prev
+		{
+			usize::MAX => self.first_position().unwrap_or(usize::MAX),
+			prev => self.live_neighbor(prev, 0),
+		};
+		
+		return Cursor {tree: self, prev, next};
+	}
+	
+	/// Removes the values with keys contained in `range` and returns the number of removed values.
+	/// # Time complexity
+	/// _O(m log n)_ where _m_ is the number of removed values and _n_ is the number of values in the tree.
+	pub fn remove_range<Key, Range>(&mut self, range: Range) -> usize
+	where
+		Type: node::Entry,
+		Type::Key: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: ?Sized + std::cmp::Ord,
+		Range: std::ops::RangeBounds<Key>,
+	{
+		use std::borrow::Borrow;
+		
+		let mut result = 0;
+		
+		loop
+		{
+			let position = self.lower_bound_position(range.start_bound());
+			
+			if position == usize::MAX || ! range.contains(self.repository[position].as_ref().key().borrow())
+			{
+				return result;
+			}
+			
+			result += self.remove_at(position).is_some() as usize;
+		}
+	}
+	
+	/// Returns the number of keys less than `key`, or less than or equal to `key` if `or_equal` is `true`.
+	fn count_less<Key>(&self, key: &Key, or_equal: bool) -> usize
+	where
+		Type: node::Entry,
+		Type::Key: std::borrow::Borrow<Key> + std::cmp::Ord,
+		Key: ?Sized + std::cmp::Ord,
+	{
+		use std::borrow::Borrow;
+		
+		let values = unsafe {self.repository.as_slice()};
+		let mut result = 0;
+		let mut index = self.root;
+		
+		while index != usize::MAX
+		{
+			let [left, right] = values[index].descendants;
+			
+			match key.cmp(values[index].as_ref().key().borrow())
+			{
+				std::cmp::Ordering::Less => index = left,
+				std::cmp::Ordering::Greater =>
+				{
+					result += node::subtree_size(values, left) + ! values[index].tombstone as usize;
+					index = right;
+				},
+				std::cmp::Ordering::Equal =>
+				{
+					result += node::subtree_size(values, left) + (or_equal && ! values[index].tombstone) as usize;
+					break;
+				},
+			}
+		}
+		
+		return result;
+	}
+	
+	pub(super) fn impl_select(&self, mut rank: usize) -> Option<&Type>
+	{
+		let values = unsafe {self.repository.as_slice()};
+		let mut index = self.root;
+		
+		while index != usize::MAX
+		{
+			let [left, right] = values[index].descendants;
+			let left_size = node::subtree_size(values, left);
+			
+			if rank < left_size
+			{
+				index = left;
+			}
+			else if rank == left_size && ! values[index].tombstone
+			{
+				return Some(values[index].as_ref());
+			}
+			else
+			{
+				rank -= left_size + ! values[index].tombstone as usize;
+				index = right;
+			}
+		}
+		
+		return None;
+	}
+	
+	pub fn pop_first(&mut self) -> Option<Type::Value>
+	where Type: node::Entry
+	{
+		self.first_position().and_then(|position| self.remove_at(position))
 	}
 	
 	pub fn pop_last(&mut self) -> Option<Type::Value>
 	where Type: node::Entry
 	{
-		if self.last != usize::MAX
+		self.last_position().and_then(|position| self.remove_at(position))
+	}
+	
+	/// Moves all the values in ascending order to consecutive positions of the underlying storage
+	/// and links them into a perfectly balanced tree.
+	/// This improves the locality of iteration after many removals.
+	/// The values removed by [remove_lazy](Self::remove_lazy) are dropped.
+	/// The capacity is retained, it can be reduced afterwards with [shrink_to_fit](Self::shrink_to_fit).
+	/// # Time complexity
+	/// _O(n)_ where _n_ is the number of values in the collection including the removed ones.
+	pub fn rebuild(&mut self)
+	where
+		Type: node::Entry,
+		Type::Key: std::cmp::Ord,
+	{
+		let mut positions = Vec::with_capacity(self.len());
+		
 		{
-			self.remove_at(self.last)
+			let mut it = node::Iterator::<&[node::Node<Type>]>
+			{
+				first: self.first,
+				last: self.last,
+				bounds: [self.first, self.last],
+				nodes: unsafe {self.repository.as_slice()},
+			};
+			
+			loop
+			{
+				let position = node::iter_impl!(it, 0);
+				
+				if position == usize::MAX
+				{
+					break;
+				}
+				
+				positions.push(position);
+			}
 		}
-		else
+		
+		let values = positions.into_iter().filter_map(|i|
 		{
-			None
+			let node = self.repository.remove(i).unwrap();
+			return if node.tombstone {None} else {Some(node.value())};
+		}).collect::<Vec<_>>();
+		self.clear();
+		
+		for value in values
+		{
+			self.repository.insert(node::Node::new(value));
 		}
+		
+		self.link_consecutive();
+	}
+	
+	/// Shrinks the capacity of the underlying storage as much as possible without moving the nodes.
+	pub fn shrink_to_fit(&mut self)
+	{
+		self.repository.shrink_to_fit();
+	}
+	
+	/// Returns a handle to the smallest value, which can be inspected and removed without searching again.
+	pub fn first_entry(&mut self) -> Option<OccupiedEntry<'_, Type>>
+	{
+		self.first_position().map(|position| OccupiedEntry {tree: self, position})
+	}
+	
+	/// Returns a handle to the largest value, which can be inspected and removed without searching again.
+	pub fn last_entry(&mut self) -> Option<OccupiedEntry<'_, Type>>
+	{
+		self.last_position().map(|position| OccupiedEntry {tree: self, position})
+	}
+	
+	/// Removes all the values from the collection and returns them in ascending order as an iterator.
+	/// The allocated capacity of the collection is retained.
+	/// If the iterator is dropped before being fully consumed, the remaining values are dropped.
+	pub fn drain(&mut self) -> Drain<'_, Type>
+	where Type: node::Entry
+	{
+		Drain {tree: self}
 	}
 }
 
@@ -241,6 +1111,546 @@ impl<Type> Default for Tree<Type>
 	fn default() -> Self {Self::new()}
 }
 
+/// A cursor pointing to a gap between two adjacent values of a [Tree] or before the first or after the last value,
+/// see [Tree::lower_bound] and [Tree::upper_bound].
+#[derive(Debug)]
+pub struct Cursor<'t, Type>
+{
+	tree: &'t Tree<Type>,
+	prev: usize,
+	next: usize,
+}
+
+impl<'t, Type> Clone for Cursor<'t, Type>
+{
+	fn clone(&self) -> Self
+	{
+		Self {tree: self.tree, prev: self.prev, next: self.next}
+	}
+}
+
+impl<'t, Type> Cursor<'t, Type>
+where Type: node::Entry
+{
+	/// Returns the value after the cursor without moving it.
+	pub fn peek_next(&self) -> Option<Type::ValueRef<'t>>
+	{
+		let tree = self.tree;
+		tree.repository.get(self.next).map(|node| node.as_ref().value_ref())
+	}
+	
+	/// Returns the value before the cursor without moving it.
+	pub fn peek_prev(&self) -> Option<Type::ValueRef<'t>>
+	{
+		let tree = self.tree;
+		tree.repository.get(self.prev).map(|node| node.as_ref().value_ref())
+	}
+	
+	/// Moves the cursor over the next value and returns it, returns [None] if the cursor is after the last value.
+	pub fn move_next(&mut self) -> Option<Type::ValueRef<'t>>
+	{
+		let result = self.peek_next()?;
+		self.prev = self.next;
+		self.next = self.tree.live_neighbor(self.next, 0);
+		return Some(result);
+	}
+	
+	/// Moves the cursor over the previous value and returns it, returns [None] if the cursor is before the first value.
+	pub fn move_prev(&mut self) -> Option<Type::ValueRef<'t>>
+	{
+		let result = self.peek_prev()?;
+		self.next = self.prev;
+		self.prev = self.tree.live_neighbor(self.prev, 1);
+		return Some(result);
+	}
+}
+
+/// A handle to a value present in a [Tree], see [Tree::first_entry].
+#[derive(Debug)]
+pub struct OccupiedEntry<'t, Type>
+{
+	pub(super) tree: &'t mut Tree<Type>,
+	pub(super) position: usize,
+}
+
+impl<'t, Type> OccupiedEntry<'t, Type>
+where Type: node::Entry
+{
+	/// Returns the position of the value, usable with the `*_at` accessors of the tree.
+	pub fn position(&self) -> usize {self.position}
+	
+	/// Returns a reference to the value.
+	pub fn get(&self) -> Type::ValueRef<'_>
+	{
+		self.tree.repository[self.position].as_ref().value_ref()
+	}
+	
+	/// Removes the value from the tree and returns it, for maps this is the pair of the key and the mapped value.
+	pub fn remove_entry(self) -> Type::Value
+	{
+		self.tree.remove_at(self.position).unwrap()
+	}
+}
+
+/// A view into a single value of a [Tree], which may be present or absent, see [Tree::entry].
+pub enum Entry<'t, Type>
+where Type: node::Entry
+{
+	Occupied(OccupiedEntry<'t, Type>),
+	Vacant(VacantEntry<'t, Type>),
+}
+
+impl<'t, Type> Entry<'t, Type>
+where Type: node::Entry
+{
+	/// Returns a reference to the key of the entry.
+	pub fn key(&self) -> &Type::Key
+	{
+		match self
+		{
+			Entry::Occupied(entry) => entry.tree.repository[entry.position].as_ref().key(),
+			Entry::Vacant(entry) => &entry.key,
+		}
+	}
+}
+
+impl<'t, Type> std::fmt::Debug for Entry<'t, Type>
+where
+	Type: node::Entry + std::fmt::Debug,
+	Type::Key: std::fmt::Debug,
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+	{
+		match self
+		{
+			Entry::Occupied(entry) => f.debug_tuple("Occupied").field(entry).finish(),
+			Entry::Vacant(entry) => f.debug_tuple("Vacant").field(entry).finish(),
+		}
+	}
+}
+
+/// Where a [VacantEntry] places its value once inserted.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Placement
+{
+	/// A new node as the descendant `parent_index` of the `parent`, as returned by [node::find].
+	Below {parent: usize, parent_index: usize},
+	/// The tombstone at the `position` is revived in place.
+	Revive {position: usize},
+}
+
+/// A handle to the location of an absent value in a [Tree], see [Tree::entry].
+#[derive(Debug)]
+pub struct VacantEntry<'t, Type>
+where Type: node::Entry
+{
+	pub(super) tree: &'t mut Tree<Type>,
+	pub(super) key: Type::Key,
+	pub(super) placement: Placement,
+}
+
+impl<'t, Type> VacantEntry<'t, Type>
+where Type: node::Entry
+{
+	/// Returns a reference to the key of the entry.
+	pub fn key(&self) -> &Type::Key {&self.key}
+	
+	/// Takes the ownership of the key.
+	pub fn into_key(self) -> Type::Key {self.key}
+	
+	/// Returns the position the value will occupy once inserted, usable with the `*_at` accessors of the tree afterwards.
+	/// The position stays valid as long as the tree is not modified in between.
+	pub fn prospective_position(&self) -> usize
+	{
+		match self.placement
+		{
+			Placement::Below {..} => self.tree.repository.peek_next_index(),
+			Placement::Revive {position} => position,
+		}
+	}
+	
+	/// Inserts the value constructed from the key at the location of the entry and returns its position.
+	pub(super) fn insert_with(self, function: impl std::ops::FnOnce(Type::Key) -> Type) -> (&'t mut Tree<Type>, usize)
+	{
+		let value = function(self.key);
+		
+		let position = match self.placement
+		{
+			Placement::Below {parent, parent_index} => self.tree.insert_new(parent, parent_index, value),
+			Placement::Revive {position} =>
+			{
+				self.tree.revive(position, value);
+				position
+			}
+		};
+		
+		return (self.tree, position);
+	}
+}
+
+/// An iterator removing the values of a [Tree] in ascending order, see [Tree::drain].
+#[derive(Debug)]
+pub struct Drain<'t, Type>
+where Type: node::Entry
+{
+	tree: &'t mut Tree<Type>,
+}
+
+impl<'t, Type> std::iter::Iterator for Drain<'t, Type>
+where Type: node::Entry
+{
+	type Item = Type::Value;
+	
+	fn next(&mut self) -> Option<Self::Item>
+	{
+		self.tree.pop_first()
+	}
+	
+	fn size_hint(&self) -> (usize, Option<usize>)
+	{
+		(self.tree.len(), Some(self.tree.len()))
+	}
+}
+
+impl<'t, Type> std::iter::DoubleEndedIterator for Drain<'t, Type>
+where Type: node::Entry
+{
+	fn next_back(&mut self) -> Option<Self::Item>
+	{
+		self.tree.pop_last()
+	}
+}
+
+impl<'t, Type> std::iter::ExactSizeIterator for Drain<'t, Type>
+where Type: node::Entry
+{
+}
+
+impl<'t, Type> std::iter::FusedIterator for Drain<'t, Type>
+where Type: node::Entry
+{
+}
+
+impl<'t, Type> Drop for Drain<'t, Type>
+where Type: node::Entry
+{
+	fn drop(&mut self)
+	{
+		self.tree.clear();
+	}
+}
+
+/// An iterator moving the values out of a [Tree] in ascending order.
+#[derive(Debug)]
+pub struct IntoIter<Type>
+{
+	tree: Tree<Type>,
+}
+
+impl<Type> std::iter::Iterator for IntoIter<Type>
+where Type: node::Entry
+{
+	type Item = Type::Value;
+	
+	fn next(&mut self) -> Option<Self::Item>
+	{
+		self.tree.pop_first()
+	}
+	
+	fn size_hint(&self) -> (usize, Option<usize>)
+	{
+		(self.tree.len(), Some(self.tree.len()))
+	}
+}
+
+impl<Type> std::iter::DoubleEndedIterator for IntoIter<Type>
+where Type: node::Entry
+{
+	fn next_back(&mut self) -> Option<Self::Item>
+	{
+		self.tree.pop_last()
+	}
+}
+
+impl<Type> std::iter::ExactSizeIterator for IntoIter<Type>
+where Type: node::Entry
+{
+}
+
+impl<Type> std::iter::FusedIterator for IntoIter<Type>
+where Type: node::Entry
+{
+}
+
+impl<Type> std::iter::IntoIterator for Tree<Type>
+where Type: node::Entry
+{
+	type Item = Type::Value;
+	type IntoIter = IntoIter<Type>;
+	
+	fn into_iter(self) -> Self::IntoIter
+	{
+		IntoIter {tree: self}
+	}
+}
+
+impl<Type> Tree<Type>
+where
+	Type: node::Entry,
+	Type::Key: std::cmp::Ord,
+{
+	/// Verifies that the tree is a valid AA-tree, panics with a descriptive message otherwise.
+	/// 
+	/// Checks the level constraints of all the nodes, consistency of the parent and descendant links,
+	/// subtree sizes, strict ordering of the keys and that `first` and `last` point to the minimum and maximum.
+	#[cfg(any(test, feature = "validate"))]
+	pub fn check_invariants(&self)
+	{
+		if let Err(message) = self.validate()
+		{
+			panic!("{}", message);
+		}
+	}
+	
+	/// Constructs a tree from its parts as returned by [into_parts](Self::into_parts) after checking that they form a valid AA-tree.
+	/// Returns a description of the first violated invariant otherwise.
+	pub fn from_parts_checked(repository: Repository<node::Node<Type>>, root: usize, first: usize, last: usize) -> Result<Self, String>
+	{
+		let result = unsafe {Self::from_parts(repository, root, first, last)};
+		result.validate()?;
+		return Ok(result);
+	}
+	
+	fn validate(&self) -> Result<(), String>
+	{
+		let mut order = Vec::with_capacity(self.repository.len());
+		
+		if self.root != usize::MAX
+		{
+			let Some(root) = self.repository.get(self.root) else
+			{
+				return Err(format!("root node {} is not present", self.root));
+			};
+			
+			if root.parent != usize::MAX
+			{
+				return Err(format!("root node {} has a parent", self.root));
+			}
+			
+			self.validate_nodes(&mut order)?;
+		}
+		
+		if self.repository.len() != order.len()
+		{
+			return Err(format!("the tree has {} reachable nodes but {} are stored", order.len(), self.repository.len()));
+		}
+		
+		let tombstones = order.iter().filter(|&&i| self.repository[i].tombstone).count();
+		
+		if self.tombstones != tombstones
+		{
+			return Err(format!("the tree has {} tombstones but {} are counted", tombstones, self.tombstones));
+		}
+		
+		for pair in order.windows(2)
+		{
+			if self.repository[pair[0]].as_ref().key() >= self.repository[pair[1]].as_ref().key()
+			{
+				return Err(format!("nodes {} and {} are not in strictly ascending order", pair[0], pair[1]));
+			}
+		}
+		
+		if order.first().copied().unwrap_or(usize::MAX) != self.first
+		{
+			return Err(String::from("first does not point to the minimum"));
+		}
+		
+		if order.last().copied().unwrap_or(usize::MAX) != self.last
+		{
+			return Err(String::from("last does not point to the maximum"));
+		}
+		
+		return Ok(());
+	}
+	
+	/// Visits the nodes in order with an explicit stack, so that a deep tree cannot overflow the call stack.
+	/// The sizes of the subtrees are computed bottom-up once both descendants of a node are finished.
+	fn validate_nodes(&self, order: &mut Vec<usize>) -> Result<(), String>
+	{
+		// The phase of a node is 0 before its left subtree, 1 before its right subtree and 2 after both
+		let mut stack = vec![(self.root, 0)];
+		// The live sizes of the finished subtrees whose parents are not finished yet
+		let mut sizes = Vec::<usize>::new();
+		
+		while let Some((index, phase)) = stack.pop()
+		{
+			if stack.len() > self.repository.len() || order.len() > self.repository.len()
+			{
+				return Err(format!("node {} is reachable through multiple paths", index));
+			}
+			
+			let node = &self.repository[index];
+			let [left, right] = node.descendants;
+			
+			if phase == 0
+			{
+				if node.level == 0
+				{
+					if left != usize::MAX
+					{
+						return Err(format!("node {} of level 0 has a left descendant", index));
+					}
+				}
+				else if left == usize::MAX || right == usize::MAX
+				{
+					return Err(format!("node {} of level {} is missing a descendant", index, node.level));
+				}
+				
+				stack.push((index, 1));
+				
+				if left != usize::MAX
+				{
+					let Some(left_node) = self.repository.get(left) else
+					{
+						return Err(format!("left descendant {} of node {} is not present", left, index));
+					};
+					
+					if left_node.parent != index
+					{
+						return Err(format!("left descendant {} of node {} has parent {}", left, index, left_node.parent));
+					}
+					
+					if left_node.level >= node.level
+					{
+						return Err(format!("left descendant {} of node {} has level {} >= {}", left, index, left_node.level, node.level));
+					}
+					
+					stack.push((left, 0));
+				}
+			}
+			else if phase == 1
+			{
+				order.push(index);
+				stack.push((index, 2));
+				
+				if right != usize::MAX
+				{
+					let Some(right_node) = self.repository.get(right) else
+					{
+						return Err(format!("right descendant {} of node {} is not present", right, index));
+					};
+					
+					if right_node.parent != index
+					{
+						return Err(format!("right descendant {} of node {} has parent {}", right, index, right_node.parent));
+					}
+					
+					if right_node.level > node.level
+					{
+						return Err(format!("right descendant {} of node {} has level {} > {}", right, index, right_node.level, node.level));
+					}
+					
+					let right_right = right_node.descendants[1];
+					
+					if right_right != usize::MAX && self.repository.get(right_right).is_some_and(|n| n.level >= node.level)
+					{
+						return Err(format!("right-right grandchild {} of node {} has level {} >= {}",
+							right_right, index, self.repository[right_right].level, node.level
+						));
+					}
+					
+					stack.push((right, 0));
+				}
+			}
+			else
+			{
+				let mut size = ! node.tombstone as usize;
+				
+				for descendant in node.descendants
+				{
+					if descendant != usize::MAX
+					{
+						size += sizes.pop().unwrap();
+					}
+				}
+				
+				if size != node.size
+				{
+					return Err(format!("node {} has subtree size {} but {} values are reachable", index, node.size, size));
+				}
+				
+				sizes.push(size);
+			}
+		}
+		
+		return Ok(());
+	}
+}
+
+impl<Type> Tree<Type>
+where
+	Type: node::Entry,
+	Type::Key: std::fmt::Debug,
+{
+	/// Writes the tree in the DOT format with the nodes in ascending order of their keys,
+	/// identified by the [Debug](std::fmt::Debug) representation of the keys.
+	/// Unlike the storage indices, the output depends only on the keys and the shape of the tree.
+	/// The shape may depend on the history of the insertions and removals, [rebuild](Self::rebuild) makes it canonical.
+	pub fn to_dot_sorted(&self, writer: &mut impl std::io::Write) -> std::io::Result<()>
+	{
+		let id = |index: usize| format!("\"{}\"", format!("{:?}", self.repository[index].as_ref().key())
+			.replace('\\', "\\\\").replace('"', "\\\"")
+		);
+		let mut it = node::Iterator::<&[node::Node<Type>]>
+		{
+			first: self.first,
+			last: self.last,
+			bounds: [self.first, self.last],
+			nodes: unsafe {self.repository.as_slice()},
+		};
+		
+		writeln!(writer, "digraph tree {{")?;
+		
+		loop
+		{
+			let i = node::iter_impl!(it, 0);
+			
+			if i == usize::MAX
+			{
+				break;
+			}
+			
+			if self.repository[i].tombstone
+			{
+				writeln!(writer, "{} [shape=record, style=dashed];", id(i))?;
+			}
+			else
+			{
+				writeln!(writer, "{} [shape=record];", id(i))?;
+			}
+			
+			for d in self.repository[i].descendants
+			{
+				if d != usize::MAX
+				{
+					writeln!(writer, "{} -> {};", id(i), id(d))?;
+				}
+			}
+			
+			let p = self.repository[i].parent;
+			
+			if p != usize::MAX
+			{
+				writeln!(writer, "{} -> {} [style=dotted];", id(i), id(p))?;
+			}
+		}
+		
+		writeln!(writer, "}}")?;
+		
+		Ok(())
+	}
+}
+
 impl<Type> Tree<Type>
 {
 	fn to_dot_node(&self, index: usize, writer: &mut impl std::io::Write) -> std::io::Result<()>
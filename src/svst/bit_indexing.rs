@@ -9,7 +9,7 @@ pub(super) const fn indices(index: usize) -> (usize, IndexType)
 }
 
 #[derive(Clone, Copy, Debug)]
-pub struct IndexIterator
+pub(crate) struct IndexIterator
 {
 	bitset: IndexType,
 }
@@ -40,6 +40,22 @@ impl Iterator for IndexIterator
 	}
 }
 
+impl DoubleEndedIterator for IndexIterator
+{
+	fn next_back(&mut self) -> Option<Self::Item>
+	{
+		if self.bitset == 0
+		{
+			return None;
+		}
+		
+		let result: Self::Item = (IndexType::BITS - 1 - self.bitset.leading_zeros()) as usize;
+		self.bitset &= ! (1 << result);
+		
+		return Some(result);
+	}
+}
+
 #[test]
 fn test_index_iterator()
 {
@@ -49,6 +65,20 @@ fn test_index_iterator()
 	assert_eq!([1, 2, 4, 6, 7], *it.collect::<Vec<_>>().as_slice());
 }
 
+#[test]
+fn test_index_iterator_rev()
+{
+	assert_eq!(0, IndexIterator::new(0).rev().count());
+	
+	let it = IndexIterator::new(0b11010110);
+	assert_eq!([7, 6, 4, 2, 1], *it.rev().collect::<Vec<_>>().as_slice());
+	
+	let mut it = IndexIterator::new(IndexType::MAX);
+	assert_eq!(Some(127), it.next_back());
+	assert_eq!(Some(0), it.next());
+	assert_eq!(126, it.count());
+}
+
 #[derive(Clone, Copy, Debug)]
 pub(super) struct TransientIndexSliceIterator
 {
@@ -106,7 +136,7 @@ impl TransientIndexSliceIterator
 	}
 }
 
-pub struct IndexSliceIterator<'t>
+pub(crate) struct IndexSliceIterator<'t>
 {
 	bitset: &'t [IndexType],
 	it: TransientIndexSliceIterator,
@@ -134,6 +164,39 @@ impl<'t> Iterator for IndexSliceIterator<'t>
 	}
 }
 
+/// Compares two leaf bitsets, such as the ones returned by [Repository::occupied_mask](crate::svst::Repository::occupied_mask).
+/// Returns an iterator over the positions set only in `new` and an iterator over the positions set only in `old`.
+/// The shorter bitset is treated as if it was extended with zeros.
+pub fn diff<'t>(old: &'t [IndexType], new: &'t [IndexType])
+	-> (impl std::iter::Iterator<Item = usize> + 't, impl std::iter::Iterator<Item = usize> + 't)
+{
+	let length = std::cmp::max(old.len(), new.len());
+	let word = |bitset: &[IndexType], i: usize| bitset.get(i).copied().unwrap_or(0);
+	
+	return (
+		(0 .. length).flat_map(move |i| IndexIterator::new(word(new, i) & ! word(old, i))
+			.map(move |pos| i * IndexType::BITS as usize + pos)
+		),
+		(0 .. length).flat_map(move |i| IndexIterator::new(word(old, i) & ! word(new, i))
+			.map(move |pos| i * IndexType::BITS as usize + pos)
+		),
+	);
+}
+
+#[test]
+fn test_diff()
+{
+	let (added, removed) = diff(&[], &[]);
+	assert_eq!(0, added.count());
+	assert_eq!(0, removed.count());
+	
+	let old = [0b1011 as IndexType, 1];
+	let new = [0b0110 as IndexType, 0, 0b100];
+	let (added, removed) = diff(&old, &new);
+	assert_eq!(vec![2, 2 * IndexType::BITS as usize + 2], added.collect::<Vec<_>>());
+	assert_eq!(vec![0, 3, IndexType::BITS as usize], removed.collect::<Vec<_>>());
+}
+
 #[test]
 fn test_index_slice_iterator()
 {
@@ -156,11 +219,9 @@ fn test_index_slice_iterator()
 	assert_eq!(2, IndexSliceIterator::new(&[1, 1]).count());
 }
 
-pub const fn level_length(mut size: usize) -> usize
+pub(crate) const fn level_length(size: usize) -> usize
 {
-	size += IndexType::BITS as usize - 1;
-	size /= IndexType::BITS as usize;
-	return size;
+	return size.div_ceil(IndexType::BITS as usize);
 }
 
 #[test]
@@ -175,7 +236,7 @@ fn test_level_length()
 	assert_eq!(2, level_length(IndexType::BITS as usize + 2));
 }
 
-pub fn index_length(mut size: usize) -> usize
+pub(crate) fn index_length(mut size: usize) -> usize
 {
 	let mut result: usize = 0;
 	
@@ -205,8 +266,8 @@ fn test_index_length()
 	assert_eq!(3, index_length(IndexType::BITS as usize + 2));
 }
 
-#[allow(dead_code)] // Kept for future use
-pub fn contains(mut index_span: &[IndexType], mut position: usize, mut size: usize) -> bool
+#[cfg(test)]
+pub(crate) fn contains(mut index_span: &[IndexType], mut position: usize, mut size: usize) -> bool
 {
 	size = level_length(size);
 	index_span = &index_span[index_span.len() - size ..];
@@ -259,7 +320,7 @@ fn test_contains()
 	}
 }
 
-pub fn erase(mut index_span: &mut [IndexType], mut position: usize, mut size: usize) -> bool
+pub(crate) fn erase(mut index_span: &mut [IndexType], mut position: usize, mut size: usize) -> bool
 {
 	let mut result = false;
 	
@@ -289,6 +350,75 @@ pub fn erase(mut index_span: &mut [IndexType], mut position: usize, mut size: us
 	return result;
 }
 
+/// Marks the position as occupied and propagates the change to the upper levels.
+/// Returns `false` if the position was already occupied.
+pub(crate) fn set(mut index_span: &mut [IndexType], mut position: usize, mut size: usize) -> bool
+{
+	let mut result = false;
+	
+	loop
+	{
+		size = level_length(size);
+		let modulus = position % IndexType::BITS as usize;
+		position /= IndexType::BITS as usize;
+		let level_begin = index_span.len() - size;
+		
+		if index_span[level_begin + position] & (1 << modulus) != 0
+		{
+			break;
+		}
+		
+		result = true;
+		
+		index_span[level_begin + position] |= 1 << modulus;
+		
+		if size <= 1 || index_span[level_begin + position] != IndexType::MAX
+		{
+			break;
+		}
+		
+		index_span = &mut index_span[.. level_begin];
+	}
+	
+	return result;
+}
+
+#[test]
+fn test_set()
+{
+	{
+		let mut arr = [0b10010100 as IndexType];
+		assert_eq!(true, set(&mut arr, 1, IndexType::BITS as usize));
+		assert_eq!([0b10010110 as IndexType], arr.as_slice());
+		assert_eq!(false, set(&mut arr, 1, IndexType::BITS as usize));
+		assert_eq!([0b10010110 as IndexType], arr.as_slice());
+	}
+	
+	{
+		let mut arr = [0, 0, IndexType::MAX & ! (1 << 5)];
+		assert_eq!(true, set(&mut arr, IndexType::BITS as usize + 5, 2 * IndexType::BITS as usize));
+		assert_eq!([0b10 as IndexType, 0, IndexType::MAX], arr.as_slice());
+	}
+	
+	{
+		let mut arr = [0 as IndexType; 3];
+		
+		for _ in 0 .. 200
+		{
+			push_front(&mut arr, 2 * IndexType::BITS as usize);
+		}
+		
+		let mut other = [0 as IndexType; 3];
+		
+		for i in 0 .. 200
+		{
+			assert_eq!(true, set(&mut other, i, 2 * IndexType::BITS as usize));
+		}
+		
+		assert_eq!(arr, other);
+	}
+}
+
 #[test]
 fn test_erase()
 {
@@ -397,12 +527,58 @@ fn test_levels()
 	*/
 }
 
-pub fn find_empty(index_span: &mut [IndexType], size: usize) -> usize
+/// Returns the position that [push_front] would occupy without modifying the index.
+/// Returns `size` if all the positions are occupied.
+pub(crate) fn find_empty(index_span: &[IndexType], size: usize) -> usize
 {
-	todo!()
+	if size == 0
+	{
+		return 0;
+	}
+	
+	let mut sizes = [0_usize; 6];
+	let mut sizes_len: usize = 0;
+	
+	{
+		let mut size = level_length(size);
+		
+		for i in 0 .. sizes.len()
+		{
+			if size == 1
+			{
+				break;
+			}
+			
+			sizes_len += 1;
+			size = level_length(size);
+			sizes[i] = size;
+		}
+		
+		for i in (0 .. sizes_len.saturating_sub(1)).rev()
+		{
+			sizes[i] += sizes[i + 1];
+		}
+	}
+	
+	let mut position = 0;
+	
+	for level in (0 ..= sizes_len).rev()
+	{
+		let offset = if level == sizes_len {0} else {sizes[level]};
+		let end = if level == 0 {index_span.len()} else {sizes[level - 1]};
+		
+		if offset + position >= end
+		{
+			return size;
+		}
+		
+		position = position * IndexType::BITS as usize + index_span[offset + position].trailing_ones() as usize;
+	}
+	
+	return std::cmp::min(position, size);
 }
 
-pub fn push_front(index_span: &mut [IndexType], size: usize) -> usize
+pub(crate) fn push_front(index_span: &mut [IndexType], size: usize) -> usize
 {
 	let mut sizes = [0_usize; 6];
 	let mut sizes_len: usize = 0;
@@ -494,12 +670,16 @@ fn test_push_front()
 		
 		for i in 0 .. capacity
 		{
+			assert_eq!(i, find_empty(&arr, capacity));
 			assert_eq!(i, push_front(&mut arr, capacity));
 			assert_ne!(0, arr[last_level_begin + i / IndexType::BITS as usize] & 1 << i % IndexType::BITS as usize);
 		}
 		
+		assert_eq!(capacity, find_empty(&arr, capacity));
+		
 		assert!(erase(&mut arr, 40_000, capacity));
 		assert!(!erase(&mut arr, 40_000, capacity));
+		assert_eq!(40_000, find_empty(&arr, capacity));
 		assert_eq!(40_000, push_front(&mut arr, capacity));
 		
 		assert!(erase(&mut arr, 40_000, capacity));
@@ -508,6 +688,7 @@ fn test_push_front()
 		assert!(erase(&mut arr, 19_999, capacity));
 		assert!(erase(&mut arr, 19_998, capacity));
 		
+		assert_eq!(19_998, find_empty(&arr, capacity));
 		assert_eq!(19_998, push_front(&mut arr, capacity));
 		assert_eq!(19_999, push_front(&mut arr, capacity));
 		assert_eq!(20_000, push_front(&mut arr, capacity));
@@ -516,7 +697,35 @@ fn test_push_front()
 	}
 }
 
-pub fn copy(source_span: &[IndexType], mut source_size: usize, target_span: &mut [IndexType], mut target_size: usize)
+#[test]
+fn test_find_empty()
+{
+	assert_eq!(0, find_empty(&[], 0));
+	assert_eq!(4, find_empty(&[0b1111], IndexType::BITS as usize));
+	assert_eq!(IndexType::BITS as usize, find_empty(&[IndexType::MAX], IndexType::BITS as usize));
+	assert_eq!(10, find_empty(&[0b11_1111_1111], 10));
+	assert_eq!(IndexType::BITS as usize + 4, find_empty(&[1, IndexType::MAX, 0b1111], 2 * IndexType::BITS as usize));
+	assert_eq!(200, find_empty(&[0b11, IndexType::MAX, (1 << 72) - 1], 200));
+	
+	for capacity in [1, 127, 128, 129, 200, 256, 16_385, 20_000]
+	{
+		let mut arr = Vec::<IndexType>::new();
+		arr.resize(index_length(capacity), 0);
+		
+		for i in 0 .. capacity
+		{
+			assert_eq!(i, find_empty(&arr, capacity));
+			push_front(&mut arr, capacity);
+		}
+		
+		assert_eq!(capacity, find_empty(&arr, capacity));
+	}
+}
+
+/// Copies the bitset tree of `source_size` bits to an empty tree of `target_size` bits, which must not be smaller.
+/// The target may have the same number of levels as the source or more,
+/// in the latter case the first bit of the level above the copied top is set if the copied top is full.
+pub(crate) fn copy(source_span: &[IndexType], mut source_size: usize, target_span: &mut [IndexType], mut target_size: usize)
 {
 	assert!(source_span.len() <= target_span.len());
 	
@@ -565,3 +774,41 @@ fn test_copy()
 		assert_eq!([1, IndexType::MAX, 0], result);
 	}
 }
+
+#[test]
+fn test_copy_levels()
+{
+	use rand::{Rng, SeedableRng};
+	let mut rng = rand::rngs::SmallRng::seed_from_u64(2437);
+	
+	let build = |size: usize, indices: &[usize]|
+	{
+		let mut result = vec![0 as IndexType; index_length(size)];
+		for &index in indices
+		{
+			set(&mut result, index, size);
+		}
+		return result;
+	};
+	
+	let bits = IndexType::BITS as usize;
+	let mut cases = vec![(200, 200), (200, 256), (bits, bits), (bits, bits + 1), (bits * bits, bits * bits), (bits * bits, bits * bits + 1)];
+	for _ in 0 .. 50
+	{
+		let source_size = rng.gen_range(1 .. 3 * bits * bits);
+		cases.push((source_size, source_size));
+		cases.push((source_size, rng.gen_range(source_size .. 4 * bits * bits)));
+	}
+	
+	for (source_size, target_size) in cases
+	{
+		for density in [0.0, 0.5, 0.99, 1.0]
+		{
+			let indices = (0 .. source_size).filter(|_| rng.gen_bool(density)).collect::<Vec<_>>();
+			let source = build(source_size, &indices);
+			let mut target = vec![0 as IndexType; index_length(target_size)];
+			copy(&source, source_size, &mut target, target_size);
+			assert_eq!(build(target_size, &indices), target, "copy from {} to {} with density {}", source_size, target_size, density);
+		}
+	}
+}
@@ -0,0 +1,157 @@
+use crate::svst::repository::Repository;
+
+/// A key of a value stored in a [GenRepository].
+/// It consists of the index of the value and the generation of its slot at the time of insertion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GenKey
+{
+	pub index: usize,
+	pub generation: u32,
+}
+
+/// A [Repository] which detects the use of stale keys.
+/// 
+/// Each slot has a generation which is incremented whenever a value is removed from it.
+/// Keys returned by `insert` remember the generation, so a key whose value was removed
+/// does not access a different value later inserted in the reused slot.
+#[derive(Debug)]
+pub struct GenRepository<Type>
+{
+	repository: Repository<Type>,
+	generations: Vec<u32>,
+}
+
+impl<Type> GenRepository<Type>
+{
+	/// Constructs a new, empty `GenRepository<Type>`.
+	pub const fn new() -> Self
+	{
+		Self
+		{
+			repository: Repository::new(),
+			generations: Vec::new(),
+		}
+	}
+	
+	/// Constructs a new, empty `GenRepository<Type>` with at least the specified capacity.
+	pub fn with_capacity(capacity: usize) -> Self
+	{
+		Self
+		{
+			repository: Repository::with_capacity(capacity),
+			generations: Vec::with_capacity(capacity),
+		}
+	}
+	
+	/// Returns the total number of values the repository can hold without reallocating.
+	pub fn capacity(&self) -> usize {self.repository.capacity()}
+	
+	/// Returns the number of values in the repository.
+	pub fn len(&self) -> usize {self.repository.len()}
+	
+	/// Returns `true` if the repository contains no values.
+	pub fn is_empty(&self) -> bool {self.repository.is_empty()}
+	
+	/// Inserts a value in the repository returning its key.
+	pub fn insert(&mut self, value: Type) -> GenKey
+	{
+		let index = self.repository.insert(value);
+		
+		if self.generations.len() <= index
+		{
+			self.generations.resize(index + 1, 0);
+		}
+		
+		return GenKey {index, generation: self.generations[index]};
+	}
+	
+	/// Removes the value associated with `key`, returning it or [None] if the key is stale.
+	pub fn remove(&mut self, key: GenKey) -> Option<Type>
+	{
+		if ! self.contains(key)
+		{
+			return None;
+		}
+		
+		self.generations[key.index] = self.generations[key.index].wrapping_add(1);
+		return self.repository.remove(key.index);
+	}
+	
+	/// Returns `true` if the value associated with `key` is present.
+	pub fn contains(&self, key: GenKey) -> bool
+	{
+		self.generations.get(key.index) == Some(&key.generation) && self.repository.get(key.index).is_some()
+	}
+	
+	pub fn get(&self, key: GenKey) -> Option<&Type>
+	{
+		if self.generations.get(key.index) != Some(&key.generation)
+		{
+			return None;
+		}
+		
+		return self.repository.get(key.index);
+	}
+	
+	pub fn get_mut(&mut self, key: GenKey) -> Option<&mut Type>
+	{
+		if self.generations.get(key.index) != Some(&key.generation)
+		{
+			return None;
+		}
+		
+		return self.repository.get_mut(key.index);
+	}
+	
+	/// Clears the repository, removing all values and invalidating all their keys.
+	pub fn clear(&mut self)
+	{
+		for i in self.repository.index_iter()
+		{
+			self.generations[i] = self.generations[i].wrapping_add(1);
+		}
+		
+		self.repository.clear();
+	}
+	
+	/// Returns an iterator over the pairs of keys and values present in the repository.
+	pub fn iter(&self) -> impl std::iter::Iterator<Item = (GenKey, &Type)>
+	{
+		self.repository.iter_indexed().map(|(index, value)| (GenKey {index, generation: self.generations[index]}, value))
+	}
+}
+
+impl<Type> Default for GenRepository<Type>
+{
+	fn default() -> Self {Self::new()}
+}
+
+#[test]
+fn test_gen_repository_stale_key()
+{
+	let mut r = GenRepository::new();
+	let a = r.insert("a");
+	let b = r.insert("b");
+	
+	assert_eq!(Some(&"a"), r.get(a));
+	assert_eq!(Some("a"), r.remove(a));
+	assert_eq!(None, r.remove(a));
+	
+	let c = r.insert("c");
+	assert_eq!(a.index, c.index);
+	assert_ne!(a, c);
+	assert_eq!(None, r.get(a));
+	assert_eq!(None, r.get_mut(a));
+	assert!(! r.contains(a));
+	assert_eq!(Some(&"c"), r.get(c));
+	assert_eq!(None, r.remove(a));
+	assert_eq!(2, r.len());
+	
+	assert!([(c, &"c"), (b, &"b")].into_iter().eq(r.iter()));
+	
+	r.clear();
+	assert_eq!(None, r.get(b));
+	let d = r.insert("d");
+	assert_eq!(None, r.get(c));
+	assert_eq!(Some(&"d"), r.get(d));
+}
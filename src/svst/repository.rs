@@ -2,6 +2,89 @@ use crate::svst::vector_storage::VectorStorage;
 use crate::svst::bit_indexing;
 #[cfg(test)] use rand::seq::SliceRandom;
 
+#[cold]
+fn capacity_overflow() -> !
+{
+	panic!("repository capacity overflow");
+}
+
+/// The state of an index of a [Repository].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryState
+{
+	/// The index holds a value.
+	Occupied,
+	/// The index is within the capacity but does not hold a value.
+	Free,
+	/// The index is not less than the capacity.
+	OutOfBounds,
+}
+
+/// A typed index of a value stored in a [Repository] of `Type`.
+/// 
+/// Keys are returned by [Repository::insert_key] and accepted by the other `_key` methods,
+/// so that an index obtained from a repository of one type cannot be used with a repository of another type:
+/// ```compile_fail
+/// let mut strings = vaults::svst::Repository::<String>::new();
+/// let mut numbers = vaults::svst::Repository::<i32>::new();
+/// let key = strings.insert_key(String::from("value"));
+/// numbers.get_key(key);
+/// ```
+pub struct Key<Type>
+{
+	index: usize,
+	_data: std::marker::PhantomData<fn() -> Type>,
+}
+
+impl<Type> Key<Type>
+{
+	/// Constructs a key from a raw index.
+	pub const fn from_raw(index: usize) -> Self
+	{
+		Self {index, _data: std::marker::PhantomData}
+	}
+	
+	/// Returns the raw index of the key.
+	pub const fn as_raw(self) -> usize {self.index}
+}
+
+impl<Type> Clone for Key<Type>
+{
+	fn clone(&self) -> Self {*self}
+}
+
+impl<Type> Copy for Key<Type> {}
+
+impl<Type> PartialEq for Key<Type>
+{
+	fn eq(&self, other: &Self) -> bool {self.index == other.index}
+}
+
+impl<Type> Eq for Key<Type> {}
+
+impl<Type> PartialOrd for Key<Type>
+{
+	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {Some(self.cmp(other))}
+}
+
+impl<Type> Ord for Key<Type>
+{
+	fn cmp(&self, other: &Self) -> std::cmp::Ordering {self.index.cmp(&other.index)}
+}
+
+impl<Type> std::hash::Hash for Key<Type>
+{
+	fn hash<Hasher: std::hash::Hasher>(&self, state: &mut Hasher) {self.index.hash(state)}
+}
+
+impl<Type> std::fmt::Debug for Key<Type>
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+	{
+		f.debug_tuple("Key").field(&self.index).finish()
+	}
+}
+
 /// A data structure holding values of type `Type`.
 /// It is backed by vector-like storage and grows dynamically, similar to [Vec].
 /// 
@@ -11,12 +94,25 @@ use crate::svst::bit_indexing;
 /// 
 /// The metadata is stored as a perfectly balanced tree of 128-bit bitsets which are stored together with the storage allocated for the values.
 /// This means that despite the `insert` and `remove` operations being _O(log n)_, the logarithm has a base of 128 and the tree is extremely flat.
+/// 
+/// Additionally the repository remembers the first leaf bitset which may have a free position.
+/// As long as that bitset is not full, `insert` claims a position in it directly without searching the tree.
+/// 
+/// Like [Vec], the repository is [Send] and [Sync] exactly when `Type` is:
+/// ```compile_fail
+/// fn assert_send<Type: Send>() {}
+/// assert_send::<vaults::svst::Repository<std::rc::Rc<i32>>>();
+/// ```
 #[derive(Debug)]
 pub struct Repository<Type>
 {
 	storage: VectorStorage,
 	len: usize,
 	index_length: usize,
+	/// All leaf bitsets before this one are full.
+	free_hint: usize,
+	capacity_limit: Option<usize>,
+	/// Owns the values, this carries the auto traits of `Type` to the repository.
 	_data: std::marker::PhantomData<Type>,
 }
 
@@ -30,27 +126,58 @@ impl<Type> Repository<Type>
 			storage: VectorStorage::new::<Type>(),
 			len: 0,
 			index_length: 0,
+			free_hint: 0,
+			capacity_limit: None,
 			_data: std::marker::PhantomData,
 		}
 	}
 	
-	/// Constructs a new, empty `Repository<Type>` with at least the specified capacity.
+	/// Constructs a new, empty `Repository<Type>` with exactly the specified capacity,
+	/// see [capacity_for](Self::capacity_for).
 	pub fn with_capacity(capacity: usize) -> Self
 	{
 		let mut result = Self::new();
-		result.reserve(capacity);
+		result.reserve_exact(capacity);
 		return result;
 	}
 	
+	/// Returns the capacity of a repository constructed by [with_capacity](Self::with_capacity) with the `requested` capacity.
+	/// The capacity is not rounded, subsequent growth caused by `insert` follows the regular growth policy.
+	pub const fn capacity_for(requested: usize) -> usize
+	{
+		requested
+	}
+	
 	/// Returns the total number of values the repository can hold without reallocating.
 	pub fn capacity(&self) -> usize {self.storage.capacity}
 	
+	/// Returns the size in bytes of the memory block allocated by the repository.
+	pub fn allocated_bytes(&self) -> usize
+	{
+		if self.capacity() == 0
+		{
+			return 0;
+		}
+		
+		return Self::layout_for(self.capacity()).0.size();
+	}
+	
+	/// Returns the size in bytes of the metadata stored in front of the values, including alignment padding.
+	pub fn overhead_bytes(&self) -> usize
+	{
+		Self::array_offset(self.index_length)
+	}
+	
 	/// Reserves capacity for at least `additional` more values to be inserted in the repository.
 	pub fn reserve(&mut self, additional: usize)
 	{
-		if self.capacity() < self.len() + additional
+		let required = self.len().checked_add(additional).unwrap_or_else(|| capacity_overflow());
+		
+		if self.capacity() < required
 		{
-			let additional_exact = VectorStorage::default_capacity_for(self.capacity(), additional + self.capacity());
+			let additional_exact = VectorStorage::default_capacity_for(self.capacity(),
+				additional.checked_add(self.capacity()).unwrap_or_else(|| capacity_overflow())
+			);
 			self.reserve_exact_unchecked(additional_exact);
 		}
 	}
@@ -58,16 +185,133 @@ impl<Type> Repository<Type>
 	/// Reserves capacity for at least `additional` more values to be inserted in the repository without excessive over-allocation.
 	pub fn reserve_exact(&mut self, additional: usize)
 	{
-		if self.capacity() < self.len() + additional
+		if self.capacity() < self.len().checked_add(additional).unwrap_or_else(|| capacity_overflow())
 		{
 			self.reserve_exact_unchecked(additional);
 		}
 	}
 	
+	/// Shrinks the capacity of the repository to the position following its last value.
+	/// The indices of the values do not change, therefore the holes in front of the last value are retained.
+	pub fn shrink_to_fit(&mut self)
+	{
+		let capacity = self.keys().last().map_or(0, |i| i + 1);
+		
+		if capacity < self.capacity()
+		{
+			self.shrink_unchecked(capacity);
+		}
+	}
+	
+	/// Shrinks the capacity of the repository to at most `capacity`, but not below the position following its last value.
+	/// Does nothing if the capacity is already at most `capacity`.
+	pub fn shrink_to(&mut self, capacity: usize)
+	{
+		let capacity = std::cmp::max(capacity, self.keys().last().map_or(0, |i| i + 1));
+		
+		if capacity < self.capacity()
+		{
+			self.shrink_unchecked(capacity);
+		}
+	}
+	
+	/// Decomposes the repository into the pointer to its allocation, its capacity and the length of its index header.
+	/// The allocation consists of the bitset header followed by the array of values.
+	/// The ownership of the allocation and of the values is transferred to the caller,
+	/// who can reconstruct the repository using [Self::from_raw_buffer].
+	/// The [capacity limit](Self::set_capacity_limit) is not part of the raw parts and is discarded,
+	/// unlike with [recycle_into](Self::recycle_into).
+	pub fn into_raw_parts(self) -> (std::ptr::NonNull<u8>, usize, usize)
+	{
+		let this = std::mem::ManuallyDrop::new(self);
+		return (this.storage.data, this.capacity(), this.index_length);
+	}
+	
+	/// Constructs a repository adopting an allocation laid out as described by [Self::into_raw_parts], without copying.
+	/// The repository has no [capacity limit](Self::set_capacity_limit).
+	/// # Safety
+	/// The allocation must have been made by the global allocator with the layout a repository of `Type` uses for `capacity` values,
+	/// `index_length` must be the length of its header and the header must be consistent with the values which are initialized.
+	/// The easiest way to satisfy these requirements is to pass the result of [Self::into_raw_parts].
+	pub unsafe fn from_raw_buffer(data: std::ptr::NonNull<u8>, capacity: usize, index_length: usize) -> Self
+	{
+		let mut result = Self::new();
+		
+		if capacity != 0
+		{
+			result.storage = VectorStorage {data, capacity};
+			result.index_length = index_length;
+			result.len = result.index_header_leaf().iter().map(|bitset| bitset.count_ones() as usize).sum();
+		}
+		
+		return result;
+	}
+	
+	/// Same as [Self::from_raw_buffer] but validates the header before adopting the allocation.
+	/// Returns [None] if `index_length` does not match `capacity` or if the upper levels of the header
+	/// do not agree with the leaf level, in that case the allocation remains owned by the caller.
+	/// # Safety
+	/// The allocation must have been made by the global allocator with the layout a repository of `Type` uses for `capacity` values
+	/// and the values at the positions marked in the leaf level of the header must be initialized.
+	pub unsafe fn try_from_raw_buffer(data: std::ptr::NonNull<u8>, capacity: usize, index_length: usize) -> Option<Self>
+	{
+		if index_length != bit_indexing::index_length(capacity)
+		{
+			return None;
+		}
+		
+		let header = std::slice::from_raw_parts(data.as_ptr().cast::<bit_indexing::IndexType>(), index_length);
+		let mut expected = vec![0; index_length];
+		
+		for i in bit_indexing::IndexSliceIterator::new(&header[index_length - bit_indexing::level_length(capacity) ..])
+		{
+			if capacity <= i
+			{
+				return None;
+			}
+			
+			bit_indexing::set(&mut expected, i, capacity);
+		}
+		
+		if header != expected
+		{
+			return None;
+		}
+		
+		return Some(Self::from_raw_buffer(data, capacity, index_length));
+	}
+	
+	/// Drops all the values and converts the repository into an empty repository of values of type `Other`.
+	/// The allocation is reused if a repository of `Other` with the same capacity has the same layout,
+	/// such as when both types have the same size and alignment, otherwise a new one is allocated with the same capacity.
+	/// The [capacity limit](Self::set_capacity_limit) is retained.
+	pub fn recycle_into<Other>(mut self) -> Repository<Other>
+	{
+		let capacity = self.capacity();
+		let capacity_limit = self.capacity_limit;
+		
+		let mut result = if capacity != 0 && Self::layout_for(capacity) == Repository::<Other>::layout_for(capacity)
+		{
+			self.clear();
+			let (data, capacity, index_length) = self.into_raw_parts();
+			unsafe {Repository::<Other>::from_raw_buffer(data, capacity, index_length)}
+		}
+		else
+		{
+			std::mem::drop(self);
+			Repository::<Other>::with_capacity(capacity)
+		};
+		
+		result.capacity_limit = capacity_limit;
+		return result;
+	}
+	
 	/// Returns a slice containing the values of the repository.
 	/// Note that the slice may contain dropped values.
 	pub unsafe fn as_slice(&self) -> &[Type]
 	{
+		// With no capacity the header is empty and the dangling pointer is aligned for `Type`,
+		// the slice is then a valid empty slice
 		unsafe {std::slice::from_raw_parts(self.storage.data.as_ptr()
 			.offset(Self::array_offset(self.index_length) as isize).cast::<Type>(), self.capacity()
 		)}
@@ -82,15 +326,57 @@ impl<Type> Repository<Type>
 		)}
 	}
 	
+	/// Returns the range of indices `[0, last + 1)` which contains all the values,
+	/// where _last_ is the highest index holding a value, or `(0, 0)` if the repository is empty.
+	pub fn get_slice_bounds(&self) -> (usize, usize)
+	{
+		let header = self.index_header_leaf();
+		
+		return match header.iter().rposition(|&bitset| bitset != 0)
+		{
+			None => (0, 0),
+			Some(i) => (0, (i + 1) * bit_indexing::IndexType::BITS as usize - header[i].leading_zeros() as usize),
+		};
+	}
+	
+	/// Returns a slice of the values if they occupy exactly the indices `[0, len)`, that is there are no holes.
+	pub fn try_get_contiguous(&self) -> Option<&[Type]>
+	{
+		if self.get_slice_bounds().1 != self.len()
+		{
+			return None;
+		}
+		
+		return Some(unsafe {&self.as_slice()[.. self.len()]});
+	}
+	
 	/// Inserts a value in the repository returning its index within the repository.
 	/// The inserted value will remain at the position of the returned index for the whole lifetime of the repository or until the value is explicitly removed.
+	/// 
+	/// # Panics
+	/// Panics with "repository capacity overflow" if the grown allocation would not fit in `isize` bytes.
+	/// Aborts if the allocation fails, like [Vec].
 	/// # Time complexity
 	/// Amortized _O(log<sub>128</sub> n)_ where _n_ is the number of values in the repository.
 	pub fn insert(&mut self, value: Type) -> usize
 	{
+		// Does not grow as long as there is a hole below the capacity
 		self.reserve(1);
 		let capacity = self.capacity();
-		let index = bit_indexing::push_front(self.index_header_mut(), capacity);
+		let bitset = self.index_header_leaf()[self.free_hint];
+		let index = self.free_hint * bit_indexing::IndexType::BITS as usize + bitset.trailing_ones() as usize;
+		
+		let index = if bitset != bit_indexing::IndexType::MAX && index < capacity
+		{
+			bit_indexing::set(self.index_header_mut(), index, capacity);
+			index
+		}
+		else
+		{
+			bit_indexing::push_front(self.index_header_mut(), capacity)
+		};
+		
+		self.free_hint = index / bit_indexing::IndexType::BITS as usize;
 		
 		unsafe
 		{
@@ -104,6 +390,110 @@ impl<Type> Repository<Type>
 		return index;
 	}
 	
+	/// Inserts all the values of the iterator returning their indices in the order of insertion.
+	/// The capacity is reserved exactly for the lower bound of the size hint at once, the free indices are filled first.
+	pub fn insert_batch<Iter>(&mut self, iter: Iter) -> Vec<usize>
+	where Iter: std::iter::IntoIterator<Item = Type>
+	{
+		let iter = iter.into_iter();
+		self.reserve_exact(iter.size_hint().0);
+		let mut result = Vec::with_capacity(iter.size_hint().0);
+		result.extend(iter.map(|value| self.insert(value)));
+		return result;
+	}
+	
+	/// Inserts a value in the repository returning its index together with a mutable reference to the inserted value.
+	/// # Time complexity
+	/// Amortized _O(log<sub>128</sub> n)_ where _n_ is the number of values in the repository.
+	pub fn insert_mut(&mut self, value: Type) -> (usize, &mut Type)
+	{
+		let index = self.insert(value);
+		return (index, unsafe {self.get_unchecked_mut(index)});
+	}
+	
+	/// Inserts a value in the repository returning its index within the repository,
+	/// or returns the value back if the repository holds as many values as the [capacity limit](Self::set_capacity_limit).
+	/// When growing, the capacity is never increased past the limit.
+	/// # Time complexity
+	/// Amortized _O(log<sub>128</sub> n)_ where _n_ is the number of values in the repository.
+	pub fn try_insert(&mut self, value: Type) -> Result<usize, Type>
+	{
+		if let Some(limit) = self.capacity_limit
+		{
+			if self.len() >= limit
+			{
+				return Err(value);
+			}
+			
+			if self.len() == self.capacity()
+			{
+				let capacity = VectorStorage::default_capacity_for(self.capacity(), self.capacity() + 1);
+				self.reserve_exact(std::cmp::min(capacity, limit) - self.len());
+			}
+		}
+		
+		return Ok(self.insert(value));
+	}
+	
+	/// Sets the maximum number of values that [try_insert](Self::try_insert) allows the repository to hold,
+	/// [None] removes the limit.
+	/// The limit does not apply to `insert` and it does not shrink the current capacity.
+	pub fn set_capacity_limit(&mut self, limit: Option<usize>)
+	{
+		self.capacity_limit = limit;
+	}
+	
+	/// Returns the limit set by [set_capacity_limit](Self::set_capacity_limit).
+	pub fn capacity_limit(&self) -> Option<usize> {self.capacity_limit}
+	
+	/// Returns the index which the next call to `insert` will return, without modifying the repository.
+	/// If the repository is full, this is the first index after the current capacity.
+	/// # Time complexity
+	/// _O(log<sub>128</sub> n)_ where _n_ is the number of values in the repository.
+	pub fn peek_next_index(&self) -> usize
+	{
+		bit_indexing::find_empty(self.index_header(), self.capacity())
+	}
+	
+	/// Returns a mutable reference to the value at the `index`, first inserting the result of `function` at that exact index if it does not hold a value.
+	/// The capacity grows according to the regular growth policy if the `index` is beyond it.
+	/// # Panics
+	/// Panics with "repository capacity overflow" if the grown allocation would not fit in `isize` bytes.
+	/// # Time complexity
+	/// _O(log<sub>128</sub> n)_ where _n_ is the number of values in the repository, amortized if the repository grows.
+	pub fn get_or_insert_with<Function>(&mut self, index: usize, function: Function) -> &mut Type
+	where Function: std::ops::FnOnce() -> Type
+	{
+		if self.entry_state(index) != EntryState::Occupied
+		{
+			if self.capacity() <= index
+			{
+				let required = index.checked_add(1).unwrap_or_else(|| capacity_overflow());
+				self.reserve_exact(VectorStorage::default_capacity_for(self.capacity(), required) - self.len());
+			}
+			
+			let value = function();
+			let capacity = self.capacity();
+			bit_indexing::set(self.index_header_mut(), index, capacity);
+			unsafe {self.as_mut_slice().as_mut_ptr().add(index).write(value)};
+			self.len += 1;
+		}
+		
+		return unsafe {self.get_unchecked_mut(index)};
+	}
+	
+	/// Returns the lowest index within the capacity which does not hold a value or [None] if the repository is full.
+	/// # Time complexity
+	/// _O(log<sub>128</sub> n)_ where _n_ is the number of values in the repository.
+	pub fn first_free_index(&self) -> Option<usize>
+	{
+		match self.peek_next_index()
+		{
+			index if index < self.capacity() => Some(index),
+			_ => None,
+		}
+	}
+	
 	/// Removes a value at _index_ from the repository, returning it or [None].
 	/// # Time complexity
 	/// _O(log<sub>128</sub> n)_ where _n_ is the number of values in the repository.
@@ -122,6 +512,24 @@ impl<Type> Repository<Type>
 			}
 			
 			self.len -= 1;
+			self.free_hint = std::cmp::min(self.free_hint, index / bit_indexing::IndexType::BITS as usize);
+		}
+		
+		return result;
+	}
+	
+	/// Removes the values at all the `indices` from the repository, returning them in the same order.
+	/// The result contains [None] for the indices which do not contain a value,
+	/// including the repeated occurrences of an index.
+	/// # Time complexity
+	/// _O(m log<sub>128</sub> n)_ where _m_ is the number of indices and _n_ is the number of values in the repository.
+	pub fn remove_many(&mut self, indices: &[usize]) -> Vec<Option<Type>>
+	{
+		let mut result = Vec::with_capacity(indices.len());
+		
+		for &index in indices
+		{
+			result.push(self.remove(index));
 		}
 		
 		return result;
@@ -135,106 +543,522 @@ impl<Type> Repository<Type>
 		let capacity = self.capacity();
 		bit_indexing::erase(self.index_header_mut(), index, capacity);
 		self.len -= 1;
+		self.free_hint = std::cmp::min(self.free_hint, index / bit_indexing::IndexType::BITS as usize);
 		return self.storage.data.as_ptr().offset(Self::array_offset(self.index_length) as isize)
 			.cast::<Type>().offset(index as isize).read()
 		;
 	}
 	
-	/// Clears the repository, removing all values.
-	pub fn clear(&mut self)
+	/// Retains only the values satisfying the predicate.
+	/// Unlike [Vec::retain], the retained values keep their indices, the removed ones leave holes.
+	pub fn retain<Function>(&mut self, mut function: Function)
+	where Function: std::ops::FnMut(&Type) -> bool
 	{
-		if self.capacity() != 0
-		{
-			self.simple_clear();
-			self.len = 0;
-			self.index_header_mut().fill(0);
-		}
+		self.retain_mut(move |value| function(value));
 	}
 	
-	/// Returns the number of values in the repository.
-	pub fn len(&self) -> usize {self.len}
-	
-	/// Returns `true` if the repository contains no values.
-	pub fn is_empty(&self) -> bool {self.len == 0}
-	
-	/// Returns an iterator over the **indices** of values present in the repository.
-	pub fn index_iter(&self) -> impl std::iter::Iterator<Item = usize> + '_
+	/// Retains only the values satisfying the predicate, passing a mutable reference to it.
+	/// The retained values keep their indices.
+	pub fn retain_mut<Function>(&mut self, mut function: Function)
+	where Function: std::ops::FnMut(&mut Type) -> bool
 	{
-		bit_indexing::IndexSliceIterator::new(&self.index_header_leaf())
+		for word in 0 .. self.index_header_leaf().len()
+		{
+			let mut bitset = self.index_header_leaf()[word];
+			
+			while bitset != 0
+			{
+				let index = word * bit_indexing::IndexType::BITS as usize + bitset.trailing_zeros() as usize;
+				bitset &= bitset - 1;
+				
+				if ! function(unsafe {self.get_unchecked_mut(index)})
+				{
+					std::mem::drop(unsafe {self.remove_unchecked(index)});
+				}
+			}
+		}
 	}
 	
-	pub unsafe fn get_unchecked(&self, index: usize) -> &Type
+	/// Calls `function` with every index and a mutable reference to its value in ascending order of the indices.
+	/// This is equivalent to iterating [iter_indexed_mut](Self::iter_indexed_mut) but walks the occupancy bitsets directly.
+	pub fn for_each_mut<Function>(&mut self, mut function: Function)
+	where Function: std::ops::FnMut(usize, &mut Type)
 	{
-		self.as_slice().get_unchecked(index)
+		for word in 0 .. self.index_header_leaf().len()
+		{
+			let mut bitset = self.index_header_leaf()[word];
+			
+			while bitset != 0
+			{
+				let index = word * bit_indexing::IndexType::BITS as usize + bitset.trailing_zeros() as usize;
+				bitset &= bitset - 1;
+				function(index, unsafe {self.get_unchecked_mut(index)});
+			}
+		}
 	}
 	
-	pub unsafe fn get_unchecked_mut(&mut self, index: usize) -> &mut Type
+	/// Calls `function` with every index and its value in a read-only pass, recording the indices for which it returns `true`,
+	/// and removes the values at the recorded indices afterwards. Returns the number of removed values.
+	/// Unlike [retain](Self::retain), `function` observes all the values before any of them is removed.
+	pub fn iter_then_remove<Function>(&mut self, mut function: Function) -> usize
+	where Function: std::ops::FnMut(usize, &Type) -> bool
 	{
-		self.as_mut_slice().get_unchecked_mut(index)
+		let indices = self.iter_indexed().filter(|&(index, value)| function(index, value)).map(|(index, _)| index)
+			.collect::<Vec<_>>()
+		;
+		
+		for &index in indices.iter()
+		{
+			std::mem::drop(unsafe {self.remove_unchecked(index)});
+		}
+		
+		return indices.len();
 	}
 	
-	pub fn get(&self, index: usize) -> Option<&Type>
+	/// Moves the values down to close the holes while preserving their relative order,
+	/// after which the values occupy the indices `0 .. len` and the value with the lowest index is at index `0`.
+	/// Calls `on_move` with the old and the new index of each moved value, in ascending order.
+	/// The capacity does not change, call [shrink_to_fit](Self::shrink_to_fit) afterwards to release it.
+	/// # Time complexity
+	/// _O(n log<sub>128</sub> n)_ where _n_ is the number of values in the repository.
+	pub fn compact_preserving_order<Function>(&mut self, mut on_move: Function)
+	where Function: std::ops::FnMut(usize, usize)
 	{
-		if index < self.capacity()
+		let capacity = self.capacity();
+		let indices = self.index_iter().collect::<Vec<_>>();
+		
+		for (to, from) in indices.into_iter().enumerate().filter(|&(to, from)| to != from)
 		{
-			let (slice_idx, mask) = bit_indexing::indices(index);
 			unsafe
 			{
-				if self.index_header_leaf().get_unchecked(slice_idx) & mask != 0
-				{
-					return Some(self.as_slice().get_unchecked(index));
-				}
+				let values = self.storage.data.as_ptr().offset(Self::array_offset(self.index_length) as isize).cast::<Type>();
+				values.offset(to as isize).write(values.offset(from as isize).read());
 			}
+			
+			// Keep the header consistent before calling back, the callback may panic
+			bit_indexing::erase(self.index_header_mut(), from, capacity);
+			bit_indexing::set(self.index_header_mut(), to, capacity);
+			on_move(from, to);
 		}
 		
-		return None;
+		self.free_hint = self.len / bit_indexing::IndexType::BITS as usize;
 	}
 	
-	pub fn get_mut(&mut self, index: usize) -> Option<&mut Type>
+	/// Inserts values generated by `function` in all the free indices up to the capacity,
+	/// making the repository fully occupied without reallocating.
+	pub fn fill_with<Function>(&mut self, mut function: Function)
+	where Function: std::ops::FnMut() -> Type
 	{
-		if index < self.capacity()
+		while self.len() < self.capacity()
 		{
-			let (slice_idx, mask) = bit_indexing::indices(index);
-			unsafe
-			{
-				if self.index_header_leaf().get_unchecked(slice_idx) & mask != 0
-				{
-					return Some(self.as_mut_slice().get_unchecked_mut(index));
-				}
-			}
+			self.insert(function());
 		}
-		
-		return None;
 	}
 	
-	/// Returns an iterator over the values present in the repository.
-	pub fn iter(&self) -> impl std::iter::Iterator<Item = &Type>
+	/// Clears the repository and fills all the indices up to the capacity with values generated by `function`.
+	pub fn reset_to<Function>(&mut self, function: Function)
+	where Function: std::ops::FnMut() -> Type
 	{
-		self.into_iter()
+		self.clear();
+		self.fill_with(function);
 	}
 	
-	/// Returns a mutable iterator over the values present in the repository.
-	pub fn iter_mut(&mut self) -> impl std::iter::Iterator<Item = &mut Type>
+	/// Takes the values out of the repository leaving it empty without any capacity.
+	/// 
+	/// ```
+	/// let mut repository = vaults::svst::Repository::new();
+	/// let index = repository.insert("value");
+	/// 
+	/// let taken = repository.take();
+	/// assert_eq!("value", taken[index]);
+	/// assert!(repository.is_empty());
+	/// assert_eq!(0, repository.capacity());
+	/// ```
+	pub fn take(&mut self) -> Self
 	{
-		self.into_iter()
+		std::mem::take(self)
 	}
 	
-	fn simple_clear(&mut self)
+	/// Clears the repository, removing all values.
+	pub fn clear(&mut self)
 	{
-		let array_offset = Self::array_offset(self.index_length);
-		
-		for i in self.index_iter()
+		if self.capacity() != 0
 		{
-			unsafe
+			self.simple_clear(|repository|
 			{
-				self.storage.data.as_ptr().offset(array_offset as isize)
-					.cast::<Type>().offset(i as isize).drop_in_place()
-				;
-			};
+				repository.len = 0;
+				repository.free_hint = 0;
+				repository.index_header_mut().fill(0);
+			});
 		}
 	}
 	
-	fn array_offset(index_length: usize) -> usize
+	/// Inserts a value in the repository returning its typed key.
+	pub fn insert_key(&mut self, value: Type) -> Key<Type>
+	{
+		Key::from_raw(self.insert(value))
+	}
+	
+	/// Returns a reference to the value associated with `key` or [None] if it has been removed.
+	pub fn get_key(&self, key: Key<Type>) -> Option<&Type>
+	{
+		self.get(key.index)
+	}
+	
+	/// Returns a mutable reference to the value associated with `key` or [None] if it has been removed.
+	pub fn get_key_mut(&mut self, key: Key<Type>) -> Option<&mut Type>
+	{
+		self.get_mut(key.index)
+	}
+	
+	/// Removes the value associated with `key`, returning it or [None] if it has already been removed.
+	pub fn remove_key(&mut self, key: Key<Type>) -> Option<Type>
+	{
+		self.remove(key.index)
+	}
+	
+	/// Returns the number of values in the repository.
+	pub fn len(&self) -> usize {self.len}
+	
+	/// Returns `true` if the repository contains no values.
+	pub fn is_empty(&self) -> bool {self.len == 0}
+	
+	/// Returns an iterator over the **indices** of values present in the repository.
+	pub fn index_iter(&self) -> impl std::iter::Iterator<Item = usize> + '_
+	{
+		bit_indexing::IndexSliceIterator::new(&self.index_header_leaf())
+	}
+	
+	/// Returns an iterator over the **indices** of values present in the repository which lie within the `range`.
+	/// Only the bitsets overlapping the `range` are visited.
+	pub fn index_range_iter(&self, range: std::ops::Range<usize>) -> impl std::iter::Iterator<Item = usize> + '_
+	{
+		const BITS: usize = bit_indexing::IndexType::BITS as usize;
+		let header = self.index_header_leaf();
+		let end = std::cmp::min(range.end, header.len() * BITS);
+		let start = std::cmp::min(range.start, end);
+		
+		(start / BITS .. (end + BITS - 1) / BITS).flat_map(move |i|
+		{
+			let mut bitset = header[i];
+			
+			if i == start / BITS
+			{
+				bitset &= bit_indexing::IndexType::MAX << (start % BITS);
+			}
+			
+			if i == end / BITS
+			{
+				bitset &= ! (bit_indexing::IndexType::MAX << (end % BITS));
+			}
+			
+			bit_indexing::IndexIterator::new(bitset).map(move |pos| i * BITS + pos)
+		})
+	}
+	
+	/// Returns an iterator over the indices less than the capacity which do not contain a value.
+	pub fn free_index_iter(&self) -> impl std::iter::Iterator<Item = usize> + '_
+	{
+		let capacity = self.capacity();
+		self.index_header_leaf().iter().enumerate()
+			.flat_map(|(i, &bitset)| bit_indexing::IndexIterator::new(! bitset)
+				.map(move |pos| i * bit_indexing::IndexType::BITS as usize + pos)
+			)
+			.take_while(move |&index| index < capacity)
+	}
+	
+	pub unsafe fn get_unchecked(&self, index: usize) -> &Type
+	{
+		self.as_slice().get_unchecked(index)
+	}
+	
+	pub unsafe fn get_unchecked_mut(&mut self, index: usize) -> &mut Type
+	{
+		self.as_mut_slice().get_unchecked_mut(index)
+	}
+	
+	/// Returns the bitset of the occupied indices, which has one element per [IndexType::BITS](bit_indexing::IndexType) indices of the capacity, rounded up.
+	/// Bit _i % 128_ of element _i / 128_ is set if and only if index _i_ holds a value.
+	pub fn leaf_bitset(&self) -> &[bit_indexing::IndexType]
+	{
+		self.index_header_leaf()
+	}
+	
+	/// Returns a copy of the [leaf_bitset](Self::leaf_bitset).
+	/// Two snapshots can be compared with [diff](crate::svst::diff).
+	pub fn occupied_mask(&self) -> Box<[bit_indexing::IndexType]>
+	{
+		self.leaf_bitset().into()
+	}
+	
+	/// Feeds the set of occupied indices into the `state`, the values are not hashed.
+	/// Repositories with values at the same indices produce the same hash regardless of their values and capacities.
+	pub fn occupancy_hash<Hasher: std::hash::Hasher>(&self, state: &mut Hasher)
+	{
+		let header = self.index_header_leaf();
+		let length = header.iter().rposition(|&bitset| bitset != 0).map_or(0, |i| i + 1);
+		std::hash::Hash::hash(&header[.. length], state);
+	}
+	
+	/// Returns whether the `index` holds a value, is a hole or lies beyond the capacity.
+	pub fn entry_state(&self, index: usize) -> EntryState
+	{
+		if index >= self.capacity()
+		{
+			return EntryState::OutOfBounds;
+		}
+		
+		let (slice_idx, mask) = bit_indexing::indices(index);
+		
+		if self.index_header_leaf()[slice_idx] & mask != 0
+		{
+			return EntryState::Occupied;
+		}
+		
+		return EntryState::Free;
+	}
+	
+	pub fn get(&self, index: usize) -> Option<&Type>
+	{
+		if index < self.capacity()
+		{
+			let (slice_idx, mask) = bit_indexing::indices(index);
+			unsafe
+			{
+				if self.index_header_leaf().get_unchecked(slice_idx) & mask != 0
+				{
+					return Some(self.as_slice().get_unchecked(index));
+				}
+			}
+		}
+		
+		return None;
+	}
+	
+	pub fn get_mut(&mut self, index: usize) -> Option<&mut Type>
+	{
+		if index < self.capacity()
+		{
+			let (slice_idx, mask) = bit_indexing::indices(index);
+			unsafe
+			{
+				if self.index_header_leaf().get_unchecked(slice_idx) & mask != 0
+				{
+					return Some(self.as_mut_slice().get_unchecked_mut(index));
+				}
+			}
+		}
+		
+		return None;
+	}
+	
+	/// Returns mutable references to the values at all the `indices` in the same order.
+	/// Returns [None] if any of the indices is not present in the repository or if any index is repeated.
+	pub fn get_many_mut(&mut self, indices: &[usize]) -> Option<Vec<&mut Type>>
+	{
+		if indices.iter().any(|&index| self.get(index).is_none())
+		{
+			return None;
+		}
+		
+		let mut sorted = indices.to_vec();
+		sorted.sort_unstable();
+		
+		if sorted.windows(2).any(|w| w[0] == w[1])
+		{
+			return None;
+		}
+		
+		let values = unsafe {self.as_mut_slice()}.as_mut_ptr();
+		return Some(indices.iter().map(|&index| unsafe {&mut *values.add(index)}).collect());
+	}
+	
+	/// Returns an iterator over the values present in the repository.
+	pub fn iter(&self) -> impl std::iter::ExactSizeIterator<Item = &Type> + std::iter::FusedIterator
+	{
+		self.into_iter()
+	}
+	
+	/// Returns a mutable iterator over the values present in the repository.
+	/// The values can be modified freely, the iterator borrows the repository so its structure cannot change meanwhile.
+	pub fn iter_mut(&mut self) -> impl std::iter::ExactSizeIterator<Item = &mut Type> + std::iter::FusedIterator
+	{
+		self.into_iter()
+	}
+	
+	/// Returns an iterator over the **indices** of values present in the repository.
+	/// This is the same as [index_iter](Self::index_iter).
+	pub fn keys(&self) -> impl std::iter::Iterator<Item = usize> + '_
+	{
+		self.index_iter()
+	}
+	
+	/// Returns an iterator over the values present in the repository.
+	/// This is the same as [iter](Self::iter).
+	pub fn values(&self) -> impl std::iter::ExactSizeIterator<Item = &Type> + std::iter::FusedIterator
+	{
+		self.iter()
+	}
+	
+	/// Returns a mutable iterator over the values present in the repository.
+	/// This is the same as [iter_mut](Self::iter_mut).
+	pub fn values_mut(&mut self) -> impl std::iter::ExactSizeIterator<Item = &mut Type> + std::iter::FusedIterator
+	{
+		self.iter_mut()
+	}
+	
+	/// Returns an iterator over the pairs of indices and values present in the repository.
+	/// 
+	/// # Examples
+	/// ```
+	/// let mut repository = vaults::svst::Repository::new();
+	/// let a = repository.insert('a');
+	/// let b = repository.insert('b');
+	/// repository.insert('c');
+	/// repository.remove(b);
+	/// 
+	/// assert!(repository.keys().zip(repository.values()).eq(repository.iter_indexed()));
+	/// assert_eq!(Some((a, &'a')), repository.iter_indexed().next());
+	/// ```
+	pub fn iter_indexed(&self) -> impl std::iter::Iterator<Item = (usize, &Type)>
+	{
+		self.index_iter().map(move |i| (i, unsafe {self.get_unchecked(i)}))
+	}
+	
+	/// Returns a shared immutable copy of the repository with the values at the same indices.
+	/// The copy can be cloned cheaply and read from other threads while this repository is being modified.
+	pub fn snapshot(&self) -> std::sync::Arc<Self>
+	where Type: Clone
+	{
+		std::sync::Arc::new(self.clone())
+	}
+	
+	/// Returns an iterator over the pairs of indices and values present in the repository in the order of the indices,
+	/// which is meant to be used directly in a `for` loop.
+	/// 
+	/// ```
+	/// let mut repository = vaults::svst::Repository::new();
+	/// repository.insert('a');
+	/// let b = repository.insert('b');
+	/// repository.insert('c');
+	/// repository.remove(b);
+	/// 
+	/// let mut pairs = Vec::new();
+	/// for (index, value) in repository.entries() {pairs.push((index, *value));}
+	/// assert_eq!(vec![(0, 'a'), (2, 'c')], pairs);
+	/// ```
+	pub fn entries(&self) -> Entries<'_, Type>
+	{
+		Entries
+		{
+			it: bit_indexing::TransientIndexSliceIterator::new(self.index_header_leaf()),
+			remaining: self.len(),
+			repository: self,
+		}
+	}
+	
+	/// Collects the values into a [HashMap](std::collections::HashMap) keyed by their indices.
+	pub fn to_index_map(&self) -> std::collections::HashMap<usize, &Type>
+	{
+		let mut result = std::collections::HashMap::with_capacity(self.len());
+		result.extend(self.iter_indexed());
+		return result;
+	}
+	
+	/// Moves the values into a [HashMap](std::collections::HashMap) keyed by their indices.
+	pub fn into_index_map(mut self) -> std::collections::HashMap<usize, Type>
+	{
+		let indices = self.keys().collect::<Vec<_>>();
+		let mut result = std::collections::HashMap::with_capacity(indices.len());
+		
+		for index in indices
+		{
+			result.insert(index, unsafe {self.remove_unchecked(index)});
+		}
+		
+		return result;
+	}
+	
+	/// Returns an iterator over the values present in the repository in groups of `size` values in the order of their indices.
+	/// The last group may be shorter.
+	/// 
+	/// # Panics
+	/// Panics if `size` is 0.
+	pub fn chunks(&self, size: usize) -> impl std::iter::Iterator<Item = Vec<&Type>>
+	{
+		assert!(size != 0, "chunk size must be non-zero");
+		let mut it = self.iter();
+		
+		std::iter::from_fn(move ||
+		{
+			let chunk = it.by_ref().take(size).collect::<Vec<_>>();
+			
+			if chunk.is_empty()
+			{
+				return None;
+			}
+			
+			return Some(chunk);
+		})
+	}
+	
+	/// Returns an iterator over the pairs of **indices** and mutable references to the values present in the repository.
+	pub fn iter_indexed_mut(&mut self) -> impl std::iter::Iterator<Item = (usize, &mut Type)>
+	{
+		let mut it = self.into_iter();
+		std::iter::from_fn(move || it.next_indexed())
+	}
+	
+	/// Drops all the values and then calls `finish`.
+	/// If dropping a value panics, the remaining values are still dropped and `finish` is still called,
+	/// the panic is resumed afterwards.
+	fn simple_clear(&mut self, finish: fn(&mut Self))
+	{
+		struct Guard<'t, Type>
+		{
+			repository: &'t mut Repository<Type>,
+			it: bit_indexing::TransientIndexSliceIterator,
+			finish: fn(&mut Repository<Type>),
+		}
+		
+		impl<'t, Type> Guard<'t, Type>
+		{
+			fn drop_remaining(&mut self)
+			{
+				let array_offset = Repository::<Type>::array_offset(self.repository.index_length);
+				
+				while let Some(i) = self.it.next(self.repository.index_header_leaf())
+				{
+					unsafe
+					{
+						self.repository.storage.data.as_ptr().offset(array_offset as isize)
+							.cast::<Type>().offset(i as isize).drop_in_place()
+						;
+					};
+				}
+			}
+		}
+		
+		impl<'t, Type> Drop for Guard<'t, Type>
+		{
+			fn drop(&mut self)
+			{
+				// Continues after a panicking value when unwinding
+				self.drop_remaining();
+				(self.finish)(self.repository);
+			}
+		}
+		
+		let mut guard = Guard
+		{
+			it: bit_indexing::TransientIndexSliceIterator::new(self.index_header_leaf()),
+			repository: self,
+			finish,
+		};
+		guard.drop_remaining();
+	}
+	
+	fn array_offset(index_length: usize) -> usize
 	{
 		let type_alignment = std::mem::align_of::<Type>();
 		return (index_length * std::mem::size_of::<bit_indexing::IndexType>() as usize
@@ -242,16 +1066,25 @@ impl<Type> Repository<Type>
 		;
 	}
 	
-	fn layout_for(capacity: usize) -> (std::alloc::Layout, usize)
+	/// Returns the layout of the allocation holding `capacity` values and the length of its header,
+	/// or [None] if the size of the allocation does not fit in `isize`.
+	fn try_layout_for(capacity: usize) -> Option<(std::alloc::Layout, usize)>
 	{
 		let alignment = std::cmp::max(
 			std::mem::align_of::<Type>(),
 			std::mem::align_of::<bit_indexing::IndexType>(),
 		);
 		let index_length = crate::svst::bit_indexing::index_length(capacity);
-		let byte_size = Self::array_offset(index_length) + std::mem::size_of::<Type>() * capacity;
+		let byte_size = std::mem::size_of::<Type>().checked_mul(capacity)?
+			.checked_add(Self::array_offset(index_length))?
+		;
 		
-		return (std::alloc::Layout::from_size_align(byte_size, alignment).unwrap(), index_length);
+		return Some((std::alloc::Layout::from_size_align(byte_size, alignment).ok()?, index_length));
+	}
+	
+	fn layout_for(capacity: usize) -> (std::alloc::Layout, usize)
+	{
+		Self::try_layout_for(capacity).unwrap_or_else(|| capacity_overflow())
 	}
 	
 	fn index_header(&self) -> &[bit_indexing::IndexType]
@@ -273,9 +1106,36 @@ impl<Type> Repository<Type>
 		&self.index_header()[self.index_length - bit_indexing::level_length(self.capacity()) .. self.index_length]
 	}
 	
+	fn shrink_unchecked(&mut self, capacity: usize)
+	{
+		let mut result = Self::new();
+		result.capacity_limit = self.capacity_limit;
+		
+		if capacity != 0
+		{
+			result.reserve_exact_unchecked(capacity);
+		}
+		
+		for i in self.index_iter()
+		{
+			unsafe
+			{
+				result.as_mut_slice().as_mut_ptr().add(i).write(self.as_slice().as_ptr().add(i).read());
+			}
+			
+			bit_indexing::set(result.index_header_mut(), i, capacity);
+			result.len += 1;
+		}
+		
+		// The values have been moved, forget them before dropping the old storage
+		self.len = 0;
+		self.index_header_mut().fill(0);
+		*self = result;
+	}
+	
 	fn reserve_exact_unchecked(&mut self, additional: usize)
 	{
-		let capacity = self.len() + additional;
+		let capacity = std::cmp::max(self.capacity(), self.len().checked_add(additional).unwrap_or_else(|| capacity_overflow()));
 		let (new_layout, index_length) = Self::layout_for(capacity);
 		let new_data = match std::ptr::NonNull::new(unsafe {std::alloc::alloc(new_layout)})
 		{
@@ -329,15 +1189,17 @@ impl<Type> Drop for Repository<Type>
 	{
 		if self.capacity() != 0
 		{
-			self.simple_clear();
-			for i in 0 .. self.index_length
+			self.simple_clear(|repository|
 			{
-				unsafe
+				for i in 0 .. repository.index_length
 				{
-					self.storage.data.as_ptr().cast::<bit_indexing::IndexType>().offset(i as isize).drop_in_place();
+					unsafe
+					{
+						repository.storage.data.as_ptr().cast::<bit_indexing::IndexType>().offset(i as isize).drop_in_place();
+					}
 				}
-			}
-			unsafe {std::alloc::dealloc(self.storage.data.as_ptr(), Self::layout_for(self.capacity()).0)};
+				unsafe {std::alloc::dealloc(repository.storage.data.as_ptr(), Self::layout_for(repository.capacity()).0)};
+			});
 		}
 	}
 }
@@ -357,12 +1219,16 @@ impl<Type> AsMut<Repository<Type>> for Repository<Type>
 	fn as_mut(&mut self) -> &mut Repository<Type> {self}
 }
 
+/// The clone holds every value at the same index as the source,
+/// therefore both repositories iterate their values in the same order.
+/// A clone made by `clone` also has the same capacity as the source,
+/// `clone_from` only grows the capacity of the target if it is not sufficient to hold the last value.
 impl<Type> Clone for Repository<Type>
 where Type: Clone
 {
 	fn clone(&self) -> Self
 	{
-		let mut result = Self::new();
+		let mut result = Self::with_capacity(self.capacity());
 		result.clone_from(self);
 		return result;
 	}
@@ -370,37 +1236,42 @@ where Type: Clone
 	fn clone_from(&mut self, source: &Self)
 	{
 		self.clear();
+		self.capacity_limit = source.capacity_limit;
+		
+		let capacity = source.keys().last().map_or(0, |i| i + 1);
 		
-		if self.capacity() < source.capacity()
+		if self.capacity() < capacity
 		{
-			let mut capacity = 0;
-			let header = source.index_header_leaf();
-			
-			for i in (0 .. header.len()).rev()
+			self.reserve_exact(capacity);
+		}
+		
+		let capacity = self.capacity();
+		
+		for (index, value) in source.iter_indexed()
+		{
+			unsafe
 			{
-				if header[i] != 0
-				{
-					capacity = bit_indexing::IndexType::BITS as usize * i;
-					break;
-				}
+				self.storage.data.as_ptr().offset(Self::array_offset(self.index_length) as isize)
+					.cast::<Type>().offset(index as isize).write(value.clone())
+				;
 			}
 			
-			if self.capacity() < capacity
-			{
-				*self = Self::with_capacity(capacity);
-			}
+			bit_indexing::set(self.index_header_mut(), index, capacity);
+			self.len += 1;
 		}
-		
-		todo!()
 	}
 }
 
+/// The capacity is reserved for the upper bound of the size hint if it is at most twice the lower bound,
+/// otherwise for the lower bound, so that a loose upper bound does not cause a huge allocation.
 impl<Type> FromIterator<Type> for Repository<Type>
 {
 	fn from_iter<T: IntoIterator<Item = Type>>(iter: T) -> Self
 	{
 		let iter = iter.into_iter();
-		let mut result = Self::with_capacity(iter.size_hint().0);
+		let (lower, upper) = iter.size_hint();
+		let capacity = upper.filter(|&upper| upper <= lower.saturating_mul(2)).unwrap_or(lower);
+		let mut result = Self::with_capacity(capacity);
 		for v in iter
 		{
 			result.insert(v);
@@ -409,55 +1280,116 @@ impl<Type> FromIterator<Type> for Repository<Type>
 	}
 }
 
-pub struct Iter<'t, Type>
+/// An iterator over the pairs of indices and values of a [Repository], see [Repository::entries].
+pub struct Entries<'t, Type>
 {
 	it: bit_indexing::TransientIndexSliceIterator,
+	remaining: usize,
 	repository: &'t Repository<Type>,
 }
 
-impl<'t, Type> std::iter::Iterator for Iter<'t, Type>
+impl<'t, Type> std::iter::Iterator for Entries<'t, Type>
 {
-	type Item = &'t Type;
+	type Item = (usize, &'t Type);
+	
 	fn next(&mut self) -> Option<Self::Item>
 	{
-		self.it.next(self.repository.index_header_leaf()).map(move |i| &self.repository[i])
+		let i = self.it.next(self.repository.index_header_leaf())?;
+		self.remaining -= 1;
+		return Some((i, unsafe {self.repository.get_unchecked(i)}));
 	}
-}
-
-impl<'t, Type> IntoIterator for &'t Repository<Type>
-{
-	type Item = &'t Type;
-	type IntoIter = Iter<'t, Type>;
 	
-	fn into_iter(self) -> Self::IntoIter
+	fn size_hint(&self) -> (usize, Option<usize>)
 	{
-		Self::IntoIter
-		{
-			it: bit_indexing::TransientIndexSliceIterator::new(self.index_header_leaf()),
-			repository: self,
-		}
+		(self.remaining, Some(self.remaining))
 	}
 }
 
-pub struct IterMut<'t, Type>
+impl<'t, Type> std::iter::ExactSizeIterator for Entries<'t, Type> {}
+impl<'t, Type> std::iter::FusedIterator for Entries<'t, Type> {}
+
+pub struct Iter<'t, Type>
 {
 	it: bit_indexing::TransientIndexSliceIterator,
-	repository: &'t mut Repository<Type>,
+	remaining: usize,
+	repository: &'t Repository<Type>,
 }
 
-impl<'t, Type> std::iter::Iterator for IterMut<'t, Type>
+impl<'t, Type> std::iter::Iterator for Iter<'t, Type>
 {
-	type Item = &'t mut Type;
+	type Item = &'t Type;
 	fn next(&mut self) -> Option<Self::Item>
 	{
 		let Some(i) = self.it.next(self.repository.index_header_leaf()) else
 		{
 			return None;
 		};
-		unsafe {Some(std::ptr::addr_of_mut!(self.repository[i]).as_mut().unwrap())}
+		self.remaining -= 1;
+		Some(&self.repository[i])
+	}
+	
+	fn size_hint(&self) -> (usize, Option<usize>)
+	{
+		(self.remaining, Some(self.remaining))
+	}
+}
+
+impl<'t, Type> std::iter::ExactSizeIterator for Iter<'t, Type> {}
+impl<'t, Type> std::iter::FusedIterator for Iter<'t, Type> {}
+
+impl<'t, Type> IntoIterator for &'t Repository<Type>
+{
+	type Item = &'t Type;
+	type IntoIter = Iter<'t, Type>;
+	
+	fn into_iter(self) -> Self::IntoIter
+	{
+		Self::IntoIter
+		{
+			it: bit_indexing::TransientIndexSliceIterator::new(self.index_header_leaf()),
+			remaining: self.len(),
+			repository: self,
+		}
+	}
+}
+
+pub struct IterMut<'t, Type>
+{
+	it: bit_indexing::TransientIndexSliceIterator,
+	remaining: usize,
+	repository: &'t mut Repository<Type>,
+}
+
+impl<'t, Type> IterMut<'t, Type>
+{
+	fn next_indexed(&mut self) -> Option<(usize, &'t mut Type)>
+	{
+		let Some(i) = self.it.next(self.repository.index_header_leaf()) else
+		{
+			return None;
+		};
+		self.remaining -= 1;
+		unsafe {Some((i, std::ptr::addr_of_mut!(self.repository[i]).as_mut().unwrap()))}
+	}
+}
+
+impl<'t, Type> std::iter::Iterator for IterMut<'t, Type>
+{
+	type Item = &'t mut Type;
+	fn next(&mut self) -> Option<Self::Item>
+	{
+		self.next_indexed().map(|(_, value)| value)
+	}
+	
+	fn size_hint(&self) -> (usize, Option<usize>)
+	{
+		(self.remaining, Some(self.remaining))
 	}
 }
 
+impl<'t, Type> std::iter::ExactSizeIterator for IterMut<'t, Type> {}
+impl<'t, Type> std::iter::FusedIterator for IterMut<'t, Type> {}
+
 impl<'t, Type> IntoIterator for &'t mut Repository<Type>
 {
 	type Item = &'t mut Type;
@@ -468,14 +1400,20 @@ impl<'t, Type> IntoIterator for &'t mut Repository<Type>
 		Self::IntoIter
 		{
 			it: bit_indexing::TransientIndexSliceIterator::new(self.index_header_leaf()),
+			remaining: self.len(),
 			repository: self,
 		}
 	}
 }
 
+// Yielded values are removed from the repository, therefore both ends search the current leaf bitsets
+// and they can never yield the same value
 pub struct IterVal<Type>
 {
-	it: bit_indexing::TransientIndexSliceIterator,
+	/// The first leaf bitset which may contain a value.
+	front: usize,
+	/// One past the last leaf bitset which may contain a value.
+	back: usize,
 	repository: Repository<Type>,
 }
 
@@ -484,15 +1422,54 @@ impl<Type> std::iter::Iterator for IterVal<Type>
 	type Item = Type;
 	fn next(&mut self) -> Option<Self::Item>
 	{
-		let Some(i) = self.it.next(self.repository.index_header_leaf()) else
+		let header = self.repository.index_header_leaf();
+		
+		while self.front < self.back && header[self.front] == 0
+		{
+			self.front += 1;
+		}
+		
+		if self.front == self.back
 		{
 			return None;
-		};
+		}
+		
+		let i = self.front * bit_indexing::IndexType::BITS as usize + header[self.front].trailing_zeros() as usize;
+		unsafe {Some(self.repository.remove_unchecked(i))}
+	}
+	
+	fn size_hint(&self) -> (usize, Option<usize>)
+	{
+		(self.repository.len(), Some(self.repository.len()))
+	}
+}
+
+impl<Type> std::iter::DoubleEndedIterator for IterVal<Type>
+{
+	fn next_back(&mut self) -> Option<Self::Item>
+	{
+		let header = self.repository.index_header_leaf();
+		
+		while self.front < self.back && header[self.back - 1] == 0
+		{
+			self.back -= 1;
+		}
+		
+		if self.front == self.back
+		{
+			return None;
+		}
 		
+		let i = (self.back - 1) * bit_indexing::IndexType::BITS as usize
+			+ (bit_indexing::IndexType::BITS - 1 - header[self.back - 1].leading_zeros()) as usize
+		;
 		unsafe {Some(self.repository.remove_unchecked(i))}
 	}
 }
 
+impl<Type> std::iter::ExactSizeIterator for IterVal<Type> {}
+impl<Type> std::iter::FusedIterator for IterVal<Type> {}
+
 impl<Type> IntoIterator for Repository<Type>
 {
 	type Item = Type;
@@ -502,7 +1479,8 @@ impl<Type> IntoIterator for Repository<Type>
 	{
 		Self::IntoIter
 		{
-			it: bit_indexing::TransientIndexSliceIterator::new(self.index_header_leaf()),
+			front: 0,
+			back: self.index_header_leaf().len(),
 			repository: self,
 		}
 	}
@@ -514,19 +1492,12 @@ impl<Type> std::ops::Index<usize> for Repository<Type>
 	
 	fn index(&self, index: usize) -> &Self::Output
 	{
-		if index < self.capacity()
+		match self.entry_state(index)
 		{
-			let (slice_idx, mask) = bit_indexing::indices(index);
-			unsafe
-			{
-				if self.index_header_leaf().get_unchecked(slice_idx) & mask != 0
-				{
-					return self.as_slice().get_unchecked(index);
-				}
-			}
+			EntryState::Occupied => unsafe {self.as_slice().get_unchecked(index)},
+			EntryState::Free => panic!("index {} does not contain a value", index),
+			EntryState::OutOfBounds => panic!("index {} is out of bounds of capacity {}", index, self.capacity()),
 		}
-		
-		panic!("index {} contains an invalid value", index);
 	}
 }
 
@@ -534,19 +1505,12 @@ impl<Type> std::ops::IndexMut<usize> for Repository<Type>
 {
 	fn index_mut(&mut self, index: usize) -> &mut Self::Output
 	{
-		if index < self.capacity()
+		match self.entry_state(index)
 		{
-			let (slice_idx, mask) = bit_indexing::indices(index);
-			unsafe
-			{
-				if self.index_header_leaf().get_unchecked(slice_idx) & mask != 0
-				{
-					return self.as_mut_slice().get_unchecked_mut(index);
-				}
-			}
+			EntryState::Occupied => unsafe {self.as_mut_slice().get_unchecked_mut(index)},
+			EntryState::Free => panic!("index {} does not contain a value", index),
+			EntryState::OutOfBounds => panic!("index {} is out of bounds of capacity {}", index, self.capacity()),
 		}
-		
-		panic!("index {} contains an invalid value", index);
 	}
 }
 
@@ -668,3 +1632,1425 @@ fn test_empty_type()
 	assert_eq!(1, r.len());
 	assert_eq!((), r[0]);
 }
+
+#[test]
+fn test_iter_size_hint()
+{
+	let mut r = Repository::new();
+	for i in 0 .. 300
+	{
+		r.insert(i);
+	}
+	for i in (0 .. 300).step_by(3)
+	{
+		r.remove(i);
+	}
+	
+	assert_eq!((200, Some(200)), r.iter().size_hint());
+	assert_eq!(200, r.iter_mut().len());
+	
+	let mut it = r.iter();
+	it.next();
+	it.next();
+	assert_eq!(198, it.len());
+	assert_eq!(198, it.count());
+	
+	let mut it = r.into_iter();
+	assert_eq!(200, it.len());
+	it.next();
+	assert_eq!((199, Some(199)), it.size_hint());
+	assert_eq!(199, it.count());
+}
+
+#[test]
+fn test_reserve_exact_with_holes()
+{
+	let mut r = Repository::new();
+	for i in 0 .. 1000
+	{
+		r.insert(i);
+	}
+	for i in 0 .. 900
+	{
+		r.remove(i);
+	}
+	
+	let capacity = r.capacity();
+	r.reserve_exact(1);
+	assert_eq!(capacity, r.capacity());
+	
+	r.reserve_exact(capacity - r.len() + 10);
+	assert!(capacity + 10 <= r.capacity());
+	assert_eq!(100, r.len());
+	
+	for i in 900 .. 1000
+	{
+		assert_eq!(Some(&i), r.get(i));
+	}
+	assert!((900 .. 1000).eq(r.index_iter()));
+}
+
+#[test]
+fn test_allocated_bytes()
+{
+	let mut r = Repository::<u8>::new();
+	assert_eq!(0, r.allocated_bytes());
+	assert_eq!(0, r.overhead_bytes());
+	
+	let mut allocated = 0;
+	
+	for i in 0 .. 1000
+	{
+		let capacity = r.capacity();
+		r.insert(i as u8);
+		
+		if capacity != r.capacity()
+		{
+			assert!(allocated < r.allocated_bytes());
+		}
+		
+		allocated = r.allocated_bytes();
+		let header_bytes = bit_indexing::index_length(r.capacity()) * std::mem::size_of::<bit_indexing::IndexType>();
+		assert_eq!(header_bytes, r.overhead_bytes());
+		assert_eq!(r.overhead_bytes() + r.capacity(), allocated);
+	}
+	
+	let r = Repository::<u64>::with_capacity(10);
+	assert_eq!(std::mem::size_of::<bit_indexing::IndexType>(), r.overhead_bytes());
+	assert_eq!(r.overhead_bytes() + 8 * r.capacity(), r.allocated_bytes());
+	
+	#[repr(align(64))]
+	struct Aligned(#[allow(dead_code)] u8);
+	let mut r = Repository::<Aligned>::new();
+	r.insert(Aligned(0));
+	assert_eq!(64, r.overhead_bytes());
+	assert_eq!(64 + 64 * r.capacity(), r.allocated_bytes());
+}
+
+#[test]
+fn test_peek_next_index()
+{
+	let mut r = Repository::new();
+	assert_eq!(0, r.peek_next_index());
+	
+	for i in 0 .. 1000
+	{
+		let index = r.peek_next_index();
+		assert_eq!(index, r.insert(i));
+	}
+	
+	let mut idxs = (0 .. 1000).collect::<Vec<usize>>();
+	idxs.shuffle(&mut rand::thread_rng());
+	
+	for _ in 0 .. 10
+	{
+		for &i in idxs[.. 100].iter()
+		{
+			r.remove(i);
+		}
+		
+		for i in 0 .. 150
+		{
+			let index = r.peek_next_index();
+			assert_eq!(index, r.insert(i));
+		}
+		
+		idxs.shuffle(&mut rand::thread_rng());
+	}
+}
+
+#[test]
+fn test_free_hint_interleaved()
+{
+	use rand::{Rng, SeedableRng};
+	
+	let mut rng = rand::rngs::SmallRng::seed_from_u64(0);
+	let mut r = Repository::new();
+	let mut present = std::collections::BTreeSet::new();
+	let mut holes = std::collections::BTreeSet::new();
+	
+	for _ in 0 .. 50_000
+	{
+		if present.is_empty() || rng.gen_range(0 .. 5) < 3
+		{
+			let expected = holes.pop_first().unwrap_or(present.len());
+			assert_eq!(expected, r.peek_next_index());
+			assert_eq!(expected, r.insert(expected));
+			present.insert(expected);
+		}
+		else
+		{
+			let index = rng.gen_range(0 .. r.capacity());
+			let removed = present.remove(&index);
+			assert_eq!(removed, r.remove(index).is_some());
+			
+			if removed && index < present.len() + holes.len()
+			{
+				holes.insert(index);
+			}
+		}
+		
+		if rng.gen_range(0 .. 10_000) == 0
+		{
+			r.clear();
+			present.clear();
+			holes.clear();
+		}
+	}
+	
+	assert_eq!(present.len(), r.len());
+	assert!(present.iter().copied().eq(r.keys()));
+}
+
+#[test]
+fn test_clone_preserves_indices()
+{
+	let mut r = Repository::new();
+	
+	for i in 0 .. 1000
+	{
+		r.insert(i.to_string());
+	}
+	
+	for i in (0 .. 1000).step_by(3)
+	{
+		r.remove(i);
+	}
+	
+	r.remove(999);
+	r.remove(998);
+	
+	let c = r.clone();
+	assert_eq!(r.len(), c.len());
+	assert!(r.iter_indexed().eq(c.iter_indexed()));
+	
+	let mut c = Repository::new();
+	for i in 0 .. 3000
+	{
+		c.insert(i.to_string());
+	}
+	
+	c.clone_from(&r);
+	assert!(r.iter_indexed().eq(c.iter_indexed()));
+	assert_eq!(r.peek_next_index(), c.peek_next_index());
+	
+	let mut d = Repository::new();
+	d.clone_from(&Repository::<String>::new());
+	assert!(d.is_empty());
+	assert_eq!(0, d.insert(String::new()));
+}
+
+#[test]
+fn test_get_many_mut()
+{
+	let mut r = (0 .. 100).collect::<Repository<i32>>();
+	r.remove(50);
+	
+	let indices = (0 .. 10).map(|i| i * 7).collect::<Vec<usize>>();
+	
+	for v in r.get_many_mut(&indices).unwrap()
+	{
+		*v += 1000;
+	}
+	
+	for i in 0 .. 100
+	{
+		if i == 50
+		{
+			assert_eq!(None, r.get(i));
+		}
+		else
+		{
+			assert_eq!(Some(&(i as i32 + if i % 7 == 0 && i < 70 {1000} else {0})), r.get(i));
+		}
+	}
+	
+	assert!(r.get_many_mut(&[3, 9, 3]).is_none());
+	assert!(r.get_many_mut(&[3, 50]).is_none());
+	assert!(r.get_many_mut(&[3, 1000]).is_none());
+	assert_eq!(Some(vec![&mut 1, &mut 1000]), r.get_many_mut(&[1, 0]));
+	assert_eq!(Some(Vec::new()), r.get_many_mut(&[]));
+}
+
+#[test]
+fn test_shrink_to_fit()
+{
+	let mut r = (0 .. 1000).map(|i| i.to_string()).collect::<Repository<String>>();
+	
+	for i in (0 .. 1000).filter(|i| i % 3 != 0 || *i > 600)
+	{
+		r.remove(i);
+	}
+	
+	r.shrink_to_fit();
+	assert_eq!(601, r.capacity());
+	assert!((0 .. 601).step_by(3).map(|i| (i, i.to_string())).eq(r.iter_indexed().map(|(i, v)| (i, v.clone()))));
+	assert_eq!(1, r.insert(String::new()));
+	
+	r.clear();
+	r.shrink_to_fit();
+	assert_eq!(0, r.capacity());
+	assert_eq!(0, r.insert(String::new()));
+}
+
+#[test]
+fn test_entry_state()
+{
+	let mut r = Repository::<i32>::with_capacity(10);
+	r.insert(0);
+	r.insert(1);
+	r.remove(0);
+	
+	assert_eq!(EntryState::Free, r.entry_state(0));
+	assert_eq!(EntryState::Occupied, r.entry_state(1));
+	assert_eq!(EntryState::Free, r.entry_state(2));
+	assert_eq!(EntryState::OutOfBounds, r.entry_state(r.capacity()));
+	assert_eq!(EntryState::OutOfBounds, Repository::<i32>::new().entry_state(0));
+	
+	assert_eq!("index 0 does not contain a value",
+		*std::panic::catch_unwind(|| r[0]).unwrap_err().downcast::<String>().unwrap()
+	);
+	assert_eq!(format!("index 1000 is out of bounds of capacity {}", r.capacity()),
+		*std::panic::catch_unwind(|| r[1000]).unwrap_err().downcast::<String>().unwrap()
+	);
+}
+
+#[test]
+fn test_remove_many()
+{
+	let mut r = (0 .. 10).collect::<Repository<i32>>();
+	
+	assert_eq!(vec![Some(3), None, Some(0), None, Some(9), None],
+		r.remove_many(&[3, usize::MAX, 0, 3, 9, r.capacity()])
+	);
+	assert_eq!(7, r.len());
+	assert_eq!(vec![None, Some(1)], r.remove_many(&[0, 1]));
+	assert_eq!(6, r.len());
+	assert_eq!(Vec::<Option<i32>>::new(), r.remove_many(&[]));
+}
+
+#[test]
+fn test_occupied_mask_diff()
+{
+	let mut r = (0 .. 300).collect::<Repository<i32>>();
+	let before = r.occupied_mask();
+	assert_eq!(bit_indexing::level_length(r.capacity()), before.len());
+	
+	r.remove(5);
+	r.remove(200);
+	r.remove(7);
+	r.insert(0);
+	for i in 0 .. 100
+	{
+		r.insert(i);
+	}
+	
+	let after = r.occupied_mask();
+	let (added, removed) = bit_indexing::diff(&before, &after);
+	assert_eq!((300 .. 398).collect::<Vec<_>>(), added.collect::<Vec<_>>());
+	assert_eq!(Vec::<usize>::new(), removed.collect::<Vec<_>>());
+	
+	r.remove(10);
+	r.remove(350);
+	let last = r.occupied_mask();
+	let (added, removed) = bit_indexing::diff(&after, &last);
+	assert_eq!(0, added.count());
+	assert_eq!(vec![10, 350], removed.collect::<Vec<_>>());
+}
+
+#[test]
+fn test_fill_with()
+{
+	let mut r = Repository::<String>::with_capacity(300);
+	let capacity = r.capacity();
+	assert!((0 .. capacity).eq(r.free_index_iter()));
+	
+	for i in 0 .. 100
+	{
+		r.insert(i.to_string());
+	}
+	for i in (0 .. 100).step_by(2)
+	{
+		r.remove(i);
+	}
+	
+	assert!((0 .. 100).step_by(2).chain(100 .. capacity).eq(r.free_index_iter()));
+	
+	r.fill_with(|| String::from("x"));
+	assert_eq!(capacity, r.capacity());
+	assert_eq!(r.capacity(), r.len());
+	assert_eq!(0, r.free_index_iter().count());
+	assert_eq!("1", r[1]);
+	assert_eq!("x", r[2]);
+	assert_eq!("x", r[capacity - 1]);
+	
+	let mut n = 0;
+	r.reset_to(|| {n += 1; n.to_string()});
+	assert_eq!(capacity, r.len());
+	assert_eq!(0, r.free_index_iter().count());
+	assert!((1 ..= capacity).map(|i| i.to_string()).eq(r.iter().cloned()));
+	
+	let mut e = Repository::<i32>::new();
+	e.fill_with(|| 0);
+	assert!(e.is_empty());
+}
+
+#[test]
+fn test_iter_fused()
+{
+	let mut r = (0 .. 3).collect::<Repository<i32>>();
+	
+	{
+		let mut it = r.iter();
+		assert_eq!(3, it.by_ref().count());
+		assert_eq!(None, it.next());
+		assert_eq!(None, it.next());
+	}
+	
+	{
+		let mut it = r.iter_mut();
+		assert_eq!(3, it.by_ref().count());
+		assert!(it.next().is_none());
+		assert!(it.next().is_none());
+	}
+	
+	let mut it = r.into_iter().fuse();
+	assert_eq!(3, it.by_ref().count());
+	assert_eq!(None, it.next());
+}
+
+#[test]
+fn test_iter_indexed_mut()
+{
+	let mut r = (0 .. 500).collect::<Repository<usize>>();
+	
+	for i in (0 .. 500).filter(|i| i % 7 == 3)
+	{
+		r.remove(i);
+	}
+	
+	let mut indices = Vec::new();
+	
+	for (i, v) in r.iter_indexed_mut()
+	{
+		indices.push(i);
+		*v += 1000 * i;
+	}
+	
+	assert!(indices.iter().copied().eq(r.index_iter()));
+	assert!(r.iter_indexed().all(|(i, &v)| v == 1001 * i));
+}
+
+#[test]
+fn test_chunks()
+{
+	let mut r = (0 .. 15).collect::<Repository<i32>>();
+	
+	for i in [0, 4, 5, 9, 14]
+	{
+		r.remove(i);
+	}
+	
+	assert_eq!(vec![vec![&1, &2, &3], vec![&6, &7, &8], vec![&10, &11, &12], vec![&13]], r.chunks(3).collect::<Vec<_>>());
+	assert_eq!(vec![3, 3, 3, 1], r.chunks(3).map(|c| c.len()).collect::<Vec<_>>());
+	assert_eq!(1, r.chunks(10).count());
+	assert_eq!(0, Repository::<i32>::new().chunks(3).count());
+}
+
+#[test]
+fn test_capacity_for()
+{
+	for n in [0, 1, 7, 100, 128, 129, 1000]
+	{
+		assert_eq!(Repository::<u8>::capacity_for(n), Repository::<u8>::with_capacity(n).capacity());
+		assert_eq!(Repository::<String>::capacity_for(n), Repository::<String>::with_capacity(n).capacity());
+	}
+	
+	let mut r = Repository::with_capacity(100);
+	r.fill_with(|| 0);
+	assert_eq!(100, r.len());
+	assert_eq!(100, r.capacity());
+	r.insert(0);
+	assert!(r.capacity() > 101);
+}
+
+#[test]
+fn test_try_insert()
+{
+	let mut r = Repository::new();
+	r.set_capacity_limit(Some(4));
+	assert_eq!(Some(4), r.capacity_limit());
+	
+	for i in 0 .. 4
+	{
+		assert_eq!(Ok(i), r.try_insert(i.to_string()));
+	}
+	
+	assert_eq!(4, r.capacity());
+	assert_eq!(Err(String::from("4")), r.try_insert(String::from("4")));
+	assert_eq!(4, r.len());
+	
+	r.remove(1);
+	assert_eq!(Ok(1), r.try_insert(String::from("x")));
+	assert_eq!(Err(String::from("y")), r.try_insert(String::from("y")));
+	
+	assert_eq!(Some(4), r.clone().capacity_limit());
+	r.shrink_to_fit();
+	assert_eq!(Some(4), r.capacity_limit());
+	
+	r.set_capacity_limit(None);
+	assert_eq!(Ok(4), r.try_insert(String::from("4")));
+	
+	let mut r = Repository::new();
+	r.set_capacity_limit(Some(1000));
+	for i in 0 .. 1000
+	{
+		assert_eq!(Ok(i), r.try_insert(i));
+	}
+	assert_eq!(1000, r.capacity());
+	assert_eq!(Err(1000), r.try_insert(1000));
+}
+
+#[test]
+fn test_into_iter_double_ended()
+{
+	let mut r = (0 .. 10).map(|i| i.to_string()).collect::<Repository<String>>();
+	let mut it = r.clone().into_iter();
+	let mut values = Vec::new();
+	
+	for i in 0 .. 10
+	{
+		values.extend(if i % 2 == 0 {it.next()} else {it.next_back()});
+	}
+	
+	assert_eq!(None, it.next());
+	assert_eq!(None, it.next_back());
+	assert_eq!(vec!["0", "9", "1", "8", "2", "7", "3", "6", "4", "5"], values);
+	
+	for i in (0 .. 10).step_by(3)
+	{
+		r.remove(i);
+	}
+	for i in 0 .. 300
+	{
+		r.insert(i.to_string());
+	}
+	
+	let expected = r.iter().cloned().collect::<Vec<_>>();
+	assert!(expected.iter().rev().cloned().eq(r.clone().into_iter().rev()));
+	
+	let mut it = r.into_iter();
+	let front = it.by_ref().take(150).collect::<Vec<_>>();
+	let back = it.rev().collect::<Vec<_>>();
+	assert!(front.into_iter().chain(back.into_iter().rev()).eq(expected.into_iter()));
+}
+
+#[test]
+fn test_from_iter_capacity()
+{
+	let r = (0 .. 1000).filter(|i| i % 2 == 0).collect::<Repository<i32>>();
+	assert_eq!(500, r.len());
+	assert!(r.capacity() < 1000);
+	
+	let r = vec![0; 300].into_iter().collect::<Repository<i32>>();
+	assert_eq!(300, r.capacity());
+	
+	let r = (0 .. 300).chain((0 .. 100).filter(|_| true)).collect::<Repository<i32>>();
+	assert_eq!(400, r.len());
+	assert_eq!(400, r.capacity());
+	
+	let r = (0 ..).take_while(|&i| i < 10).collect::<Repository<i32>>();
+	assert_eq!(10, r.len());
+	
+	let r = (0 .. usize::MAX).take_while(|&x| x < 10).collect::<Repository<_>>();
+	assert_eq!(10, r.len());
+	
+	let r = (0_u32 .. 2_000_000_000).take_while(|&x| x < 10).filter(|&x| x < 3).collect::<Repository<_>>();
+	assert_eq!(3, r.len());
+	assert!(r.capacity() < 10);
+}
+
+#[test]
+fn test_occupancy_hash()
+{
+	use std::hash::Hasher;
+	
+	let hash = |r: &Repository<String>|
+	{
+		let mut hasher = std::collections::hash_map::DefaultHasher::new();
+		r.occupancy_hash(&mut hasher);
+		return hasher.finish();
+	};
+	
+	let mut a = Repository::new();
+	let mut b = Repository::with_capacity(1000);
+	
+	for i in 0 .. 300
+	{
+		a.insert(i.to_string());
+		b.insert(String::new());
+	}
+	for i in (0 .. 300).step_by(7)
+	{
+		a.remove(i);
+		b.remove(i);
+	}
+	
+	assert_eq!(hash(&a), hash(&b));
+	assert_eq!(hash(&Repository::new()), hash(&Repository::with_capacity(10)));
+	
+	b.remove(1);
+	assert_ne!(hash(&a), hash(&b));
+	a.remove(1);
+	assert_eq!(hash(&a), hash(&b));
+}
+
+#[test]
+fn test_insert_churn_capacity()
+{
+	use rand::{Rng, SeedableRng};
+	let mut rng = rand::rngs::SmallRng::seed_from_u64(2395);
+	let mut repository = Repository::<usize>::new();
+	let mut indices = (0 .. 1000).map(|v| repository.insert(v)).collect::<Vec<_>>();
+	let capacity = repository.capacity();
+	
+	for v in 0 .. 100_000
+	{
+		let i = rng.gen_range(0 .. indices.len());
+		repository.remove(indices[i]).unwrap();
+		indices[i] = repository.insert(v);
+		assert_eq!(capacity, repository.capacity());
+	}
+	
+	assert_eq!(1000, repository.len());
+}
+
+#[test]
+fn test_try_get_contiguous()
+{
+	let mut repository = Repository::<i32>::new();
+	assert_eq!((0, 0), repository.get_slice_bounds());
+	assert_eq!(Some(&[][..]), repository.try_get_contiguous());
+	
+	for v in 0 .. 300
+	{
+		repository.insert(v);
+	}
+	
+	assert_eq!((0, 300), repository.get_slice_bounds());
+	assert_eq!(Some(&(0 .. 300).collect::<Vec<_>>()[..]), repository.try_get_contiguous());
+	
+	repository.remove(200);
+	assert_eq!((0, 300), repository.get_slice_bounds());
+	assert_eq!(None, repository.try_get_contiguous());
+	
+	repository.insert(200);
+	repository.remove(299);
+	assert_eq!((0, 299), repository.get_slice_bounds());
+	assert_eq!(Some(&(0 .. 299).collect::<Vec<_>>()[..]), repository.try_get_contiguous());
+}
+
+#[test]
+fn test_send_sync()
+{
+	fn assert_send_sync<Type: Send + Sync>() {}
+	assert_send_sync::<Repository<i32>>();
+	assert_send_sync::<Repository<std::sync::Arc<i32>>>();
+	assert_send_sync::<crate::svst::AATreeMap<i32, String>>();
+}
+
+#[test]
+fn test_retain_mut()
+{
+	let mut repository = Repository::<usize>::new();
+	for v in 0 .. 1000
+	{
+		repository.insert(v);
+	}
+	
+	repository.remove(500);
+	repository.retain_mut(|v|
+	{
+		*v += 1;
+		return *v % 2 == 1;
+	});
+	
+	assert_eq!(499, repository.len());
+	assert!(repository.keys().eq((0 .. 1000).step_by(2).filter(|&i| i != 500)));
+	assert!(repository.iter_indexed().all(|(i, &v)| v == i + 1));
+	
+	repository.retain(|&v| v < 100);
+	assert!(repository.keys().eq((0 .. 100).step_by(2)));
+	assert_eq!(Some(&1), repository.get(0));
+	assert_eq!(None, repository.get(1));
+}
+
+#[test]
+fn test_insert_mut()
+{
+	let mut repository = Repository::<(usize, String)>::new();
+	repository.insert((0, String::new()));
+	
+	let (index, value) = repository.insert_mut((0, String::new()));
+	value.0 = index;
+	value.1.push_str("inserted");
+	
+	assert_eq!(1, index);
+	assert_eq!(Some(&(1, String::from("inserted"))), repository.get(index));
+}
+
+#[test]
+fn test_clone_capacity()
+{
+	let mut repository = Repository::<i32>::new();
+	for v in 0 .. 1000
+	{
+		repository.insert(v);
+	}
+	for i in (0 .. 1000).step_by(3).chain(900 .. 1000)
+	{
+		repository.remove(i);
+	}
+	
+	let clone = repository.clone();
+	assert_eq!(repository.capacity(), clone.capacity());
+	assert_eq!(repository.occupied_mask(), clone.occupied_mask());
+	assert!(repository.iter_indexed().eq(clone.iter_indexed()));
+	
+	let clone = Repository::<i32>::new().clone();
+	assert_eq!(0, clone.capacity());
+}
+
+#[test]
+fn test_to_index_map()
+{
+	let mut repository = Repository::<String>::new();
+	for v in 0 .. 300
+	{
+		repository.insert(v.to_string());
+	}
+	for i in (0 .. 300).step_by(7)
+	{
+		repository.remove(i);
+	}
+	
+	let map = repository.to_index_map();
+	assert_eq!(repository.len(), map.len());
+	assert_eq!(repository.keys().collect::<std::collections::HashSet<_>>(), map.keys().copied().collect());
+	assert!(repository.iter_indexed().all(|(i, v)| map[&i] == v));
+	
+	let expected = repository.keys().collect::<std::collections::HashSet<_>>();
+	let map = repository.into_index_map();
+	assert_eq!(expected, map.keys().copied().collect());
+	assert!(map.iter().all(|(i, v)| *v == i.to_string()));
+}
+
+#[test]
+fn test_zero_capacity()
+{
+	let check = |repository: &mut Repository<String>|
+	{
+		assert_eq!(0, repository.capacity());
+		assert_eq!(0, repository.len());
+		assert!(repository.is_empty());
+		assert_eq!(None, repository.get(0));
+		assert_eq!(None, repository.get_mut(0));
+		assert_eq!(EntryState::OutOfBounds, repository.entry_state(0));
+		assert_eq!(0, repository.iter().count());
+		assert_eq!(0, repository.iter_mut().count());
+		assert_eq!(0, repository.values().count());
+		assert_eq!(0, repository.keys().count());
+		assert_eq!(0, repository.free_index_iter().count());
+		assert_eq!(0, repository.iter_indexed().count());
+		assert_eq!(0, repository.iter_indexed_mut().count());
+		assert_eq!(0, repository.chunks(4).count());
+		assert!(repository.occupied_mask().is_empty());
+		assert_eq!((0, 0), repository.get_slice_bounds());
+		assert_eq!(Some(&[][..]), repository.try_get_contiguous());
+		assert!(unsafe {repository.as_slice()}.is_empty());
+		assert!(unsafe {repository.as_mut_slice()}.is_empty());
+		assert_eq!(0, repository.peek_next_index());
+		assert!(repository.to_index_map().is_empty());
+		assert_eq!(0, repository.clone().capacity());
+		repository.retain(|_| false);
+		repository.shrink_to_fit();
+		repository.clear();
+		assert_eq!(None, repository.remove(0));
+		
+		let mut hasher = std::collections::hash_map::DefaultHasher::new();
+		repository.occupancy_hash(&mut hasher);
+	};
+	
+	let mut repository = Repository::<String>::new();
+	check(&mut repository);
+	
+	repository.insert(String::from("value"));
+	repository.clear();
+	repository.shrink_to_fit();
+	check(&mut repository);
+	
+	assert_eq!(0, repository.insert(String::from("value")));
+}
+
+#[test]
+fn test_clear_panicking_drop()
+{
+	struct Droppable<'t>
+	{
+		value: usize,
+		panic_on: usize,
+		dropped: &'t std::cell::Cell<usize>,
+	}
+	
+	impl<'t> Drop for Droppable<'t>
+	{
+		fn drop(&mut self)
+		{
+			self.dropped.set(self.dropped.get() + 1);
+			
+			if self.value == self.panic_on
+			{
+				panic!("dropping {}", self.value);
+			}
+		}
+	}
+	
+	let dropped = std::cell::Cell::new(0);
+	let mut repository = Repository::new();
+	for value in 0 .. 300
+	{
+		repository.insert(Droppable {value, panic_on: 100, dropped: &dropped});
+	}
+	
+	let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| repository.clear()));
+	assert!(result.is_err());
+	assert_eq!(300, dropped.get());
+	assert!(repository.is_empty());
+	assert_eq!(0, repository.iter().count());
+	
+	dropped.set(0);
+	for value in 0 .. 300
+	{
+		repository.insert(Droppable {value, panic_on: 200, dropped: &dropped});
+	}
+	
+	let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || std::mem::drop(repository)));
+	assert!(result.is_err());
+	assert_eq!(300, dropped.get());
+}
+
+#[test]
+fn test_index_range_iter()
+{
+	use rand::{Rng, SeedableRng};
+	let mut rng = rand::rngs::SmallRng::seed_from_u64(2414);
+	let mut repository = Repository::<usize>::new();
+	for v in 0 .. 1000
+	{
+		repository.insert(v);
+	}
+	for _ in 0 .. 600
+	{
+		repository.remove(rng.gen_range(0 .. 1000));
+	}
+	
+	assert!(repository.index_range_iter(10 .. 20).eq(repository.keys().filter(|i| (10 .. 20).contains(i))));
+	
+	for _ in 0 .. 1000
+	{
+		let start = rng.gen_range(0 .. 1200);
+		let end = rng.gen_range(0 .. 1200);
+		assert!(repository.index_range_iter(start .. end).eq(repository.keys().filter(|i| (start .. end).contains(i))));
+	}
+	
+	for (start, end) in [(0, 0), (0, 128), (128, 256), (127, 129), (0, usize::MAX), (usize::MAX, usize::MAX)]
+	{
+		assert!(repository.index_range_iter(start .. end).eq(repository.keys().filter(|i| (start .. end).contains(i))));
+	}
+	
+	assert_eq!(0, Repository::<usize>::new().index_range_iter(0 .. 10).count());
+}
+
+#[test]
+fn test_snapshot()
+{
+	let mut repository = Repository::<String>::new();
+	for v in 0 .. 300
+	{
+		repository.insert(v.to_string());
+	}
+	
+	let snapshot = repository.snapshot();
+	let expected = snapshot.iter_indexed().map(|(i, v)| (i, v.clone())).collect::<Vec<_>>();
+	
+	repository.remove(5);
+	repository[6].push('!');
+	repository.insert(String::from("new"));
+	repository.insert(String::from("new"));
+	
+	let threads = (0 .. 4).map(|_|
+	{
+		let snapshot = std::sync::Arc::clone(&snapshot);
+		std::thread::spawn(move || snapshot.iter_indexed().map(|(i, v)| (i, v.clone())).collect::<Vec<_>>())
+	}).collect::<Vec<_>>();
+	
+	for thread in threads
+	{
+		assert_eq!(expected, thread.join().unwrap());
+	}
+	
+	assert_eq!(Some(&String::from("5")), snapshot.get(5));
+	assert_eq!(Some(&String::from("6")), snapshot.get(6));
+	assert_eq!(300, snapshot.len());
+	assert_eq!(301, repository.len());
+}
+
+#[test]
+fn test_static()
+{
+	static REPOSITORY: std::sync::Mutex<Repository<String>> = std::sync::Mutex::new(Repository::new());
+	const EMPTY: Repository<(u8, u128)> = Repository::new();
+	
+	let index = REPOSITORY.lock().unwrap().insert(String::from("value"));
+	let thread = std::thread::spawn(move || REPOSITORY.lock().unwrap().remove(index));
+	assert_eq!(Some(String::from("value")), thread.join().unwrap());
+	assert!(REPOSITORY.lock().unwrap().is_empty());
+	assert_eq!(0, EMPTY.capacity());
+}
+
+#[test]
+#[should_panic(expected = "repository capacity overflow")]
+fn test_capacity_overflow_exact()
+{
+	Repository::<u64>::new().reserve_exact(usize::MAX / 4);
+}
+
+#[test]
+#[should_panic(expected = "repository capacity overflow")]
+fn test_capacity_overflow()
+{
+	let mut repository = Repository::<u8>::new();
+	repository.insert(0);
+	repository.reserve(usize::MAX);
+}
+
+#[test]
+fn test_iter_then_remove()
+{
+	let mut repository = Repository::<i32>::new();
+	for v in -150 .. 150
+	{
+		repository.insert(if v % 3 == 0 {-v} else {v});
+	}
+	let expected = repository.iter_indexed().filter(|(_, &v)| v >= 0).map(|(i, &v)| (i, v)).collect::<Vec<_>>();
+	
+	let mut seen = 0;
+	assert_eq!(300 - expected.len(), repository.iter_then_remove(|_, &v|
+	{
+		seen += 1;
+		return v < 0;
+	}));
+	assert_eq!(300, seen);
+	assert!(repository.iter_indexed().map(|(i, &v)| (i, v)).eq(expected));
+	assert_eq!(0, repository.iter_then_remove(|_, &v| v < 0));
+}
+
+#[test]
+fn test_insert_batch()
+{
+	let mut repository = Repository::<usize>::new();
+	assert_eq!((0 .. 200).collect::<Vec<_>>(), repository.insert_batch(0 .. 200));
+	assert_eq!(200, repository.capacity());
+	
+	for i in [3, 150, 7]
+	{
+		repository.remove(i);
+	}
+	
+	let indices = repository.insert_batch(1000 .. 1005);
+	assert_eq!(vec![3, 7, 150, 200, 201], indices);
+	assert_eq!(202, repository.capacity());
+	assert!(indices.iter().zip(1000 ..).all(|(&i, v)| repository[i] == v));
+	assert!(repository.insert_batch(std::iter::empty()).is_empty());
+}
+
+#[test]
+fn test_take()
+{
+	let mut repository = Repository::<String>::new();
+	repository.set_capacity_limit(Some(300));
+	for v in 0 .. 200
+	{
+		repository.insert(v.to_string());
+	}
+	repository.remove(10);
+	
+	let taken = repository.take();
+	assert_eq!(199, taken.len());
+	assert_eq!(None, taken.get(10));
+	assert_eq!(Some(&String::from("11")), taken.get(11));
+	assert_eq!(Some(300), taken.capacity_limit());
+	
+	assert!(repository.is_empty());
+	assert_eq!(0, repository.capacity());
+	assert_eq!(None, repository.capacity_limit());
+	assert_eq!(0, repository.insert(String::new()));
+}
+
+#[test]
+fn test_entries()
+{
+	let mut repository = Repository::<usize>::new();
+	for v in 0 .. 500
+	{
+		repository.insert(v * 2);
+	}
+	for i in (0 .. 500).step_by(3)
+	{
+		repository.remove(i);
+	}
+	
+	assert_eq!(repository.len(), repository.entries().len());
+	assert!(repository.entries().eq(repository.iter_indexed()));
+	
+	let mut count = 0;
+	for (i, v) in repository.entries()
+	{
+		assert_eq!(i * 2, *v);
+		assert_ne!(0, i % 3);
+		count += 1;
+	}
+	assert_eq!(repository.len(), count);
+}
+
+#[test]
+fn test_shrink_to()
+{
+	let mut repository = Repository::<String>::new();
+	for i in 0 .. 1000
+	{
+		repository.insert(i.to_string());
+	}
+	for i in 100 .. 1000
+	{
+		repository.remove(i);
+	}
+	repository.remove(50);
+	
+	repository.shrink_to(2000);
+	assert!(repository.capacity() >= 1000);
+	
+	repository.shrink_to(10);
+	assert_eq!(100, repository.capacity());
+	assert_eq!(99, repository.len());
+	assert!(repository.entries().all(|(i, v)| *v == i.to_string()));
+	
+	repository.clear();
+	repository.shrink_to(10);
+	assert_eq!(10, repository.capacity());
+	assert_eq!(0, repository.insert(String::from("0")));
+}
+
+#[test]
+fn test_key()
+{
+	let mut repository = Repository::<String>::new();
+	let keys = (0 .. 100).map(|i| repository.insert_key(i.to_string())).collect::<Vec<_>>();
+	
+	for (i, key) in keys.iter().enumerate()
+	{
+		assert_eq!(i, key.as_raw());
+		assert_eq!(*key, Key::from_raw(i));
+		assert_eq!(Some(&i.to_string()), repository.get_key(*key));
+	}
+	
+	repository.get_key_mut(keys[5]).unwrap().push('!');
+	assert_eq!("5!", repository[keys[5].as_raw()]);
+	assert_eq!(Some(String::from("5!")), repository.remove_key(keys[5]));
+	assert_eq!(None, repository.remove_key(keys[5]));
+	assert_eq!(None, repository.get_key(keys[5]));
+	assert_eq!(keys[5], repository.insert_key(String::new()));
+	assert_eq!("Key(7)", format!("{:?}", keys[7]));
+	
+	let set = keys.iter().copied().collect::<std::collections::HashSet<_>>();
+	assert_eq!(100, set.len());
+	assert!(keys.iter().max() == Some(&keys[99]));
+}
+
+#[test]
+fn test_raw_buffer()
+{
+	let mut repository = Repository::<String>::new();
+	for i in 0 .. 1000
+	{
+		repository.insert(i.to_string());
+	}
+	for i in (0 .. 1000).step_by(7)
+	{
+		repository.remove(i);
+	}
+	repository.set_capacity_limit(Some(2000));
+	let expected = repository.clone();
+	
+	let (data, capacity, index_length) = repository.into_raw_parts();
+	let repository = unsafe {Repository::<String>::from_raw_buffer(data, capacity, index_length)};
+	assert_eq!(None, repository.capacity_limit());
+	assert_eq!(expected.len(), repository.len());
+	assert_eq!(capacity, repository.capacity());
+	assert!(repository.entries().eq(expected.entries()));
+	
+	let (data, capacity, index_length) = repository.into_raw_parts();
+	assert!(unsafe {Repository::<String>::try_from_raw_buffer(data, capacity, index_length + 1)}.is_none());
+	
+	// Mark a full leaf as not full in the level above
+	let header = unsafe {std::slice::from_raw_parts_mut(data.as_ptr().cast::<bit_indexing::IndexType>(), index_length)};
+	let leaf = index_length - bit_indexing::level_length(capacity);
+	let saved = header[leaf];
+	header[leaf] = bit_indexing::IndexType::MAX;
+	assert!(unsafe {Repository::<String>::try_from_raw_buffer(data, capacity, index_length)}.is_none());
+	header[leaf] = saved;
+	
+	let mut repository = unsafe {Repository::<String>::try_from_raw_buffer(data, capacity, index_length)}.unwrap();
+	assert!(repository.entries().eq(expected.entries()));
+	assert_eq!(0, repository.insert(String::from("new")));
+	
+	let (data, capacity, index_length) = Repository::<String>::new().into_raw_parts();
+	let repository = unsafe {Repository::<String>::try_from_raw_buffer(data, capacity, index_length)}.unwrap();
+	assert_eq!(0, repository.capacity());
+}
+
+#[test]
+fn test_compact_preserving_order()
+{
+	let mut repository = Repository::<String>::new();
+	for i in 0 .. 10
+	{
+		repository.insert(i.to_string());
+	}
+	for i in (1 .. 10).step_by(2)
+	{
+		repository.remove(i);
+	}
+	
+	let mut moves = Vec::new();
+	repository.compact_preserving_order(|from, to| moves.push((from, to)));
+	assert_eq!(vec![(2, 1), (4, 2), (6, 3), (8, 4)], moves);
+	assert_eq!(5, repository.len());
+	assert!(repository.index_iter().eq(0 .. 5));
+	assert!(repository.iter().eq(["0", "2", "4", "6", "8"]));
+	assert_eq!(5, repository.insert(String::from("new")));
+	
+	repository.compact_preserving_order(|_, _| panic!());
+	
+	let mut repository = Repository::<usize>::new();
+	for i in 0 .. 1000
+	{
+		repository.insert(i);
+	}
+	for i in (0 .. 1000).filter(|i| i % 3 != 0 || i % 7 == 0)
+	{
+		repository.remove(i);
+	}
+	let expected = repository.iter().copied().collect::<Vec<_>>();
+	repository.compact_preserving_order(|from, to| assert!(to < from));
+	assert!(repository.iter().eq(expected.iter()));
+	assert!(repository.index_iter().eq(0 .. expected.len()));
+	assert_eq!(expected.len(), repository.insert(0));
+	
+	repository.shrink_to_fit();
+	assert_eq!(expected.len() + 1, repository.capacity());
+}
+
+#[test]
+fn test_with_capacity_exact()
+{
+	let mut repository = Repository::<u64>::with_capacity(130);
+	assert_eq!(130, repository.capacity());
+	let data = repository.storage.data;
+	
+	for i in 0 .. 130
+	{
+		repository.insert(i);
+	}
+	assert_eq!(130, repository.capacity());
+	assert_eq!(data, repository.storage.data);
+	
+	repository.insert(130);
+	assert!(repository.capacity() > 131);
+}
+
+#[test]
+fn test_first_free_index()
+{
+	assert_eq!(None, Repository::<i32>::new().first_free_index());
+	
+	for capacity in [1, 100, 128, 129, 1000, 20000]
+	{
+		let mut repository = Repository::<usize>::with_capacity(capacity);
+		assert_eq!(Some(0), repository.first_free_index());
+		
+		repository.fill_with(|| 0);
+		assert_eq!(None, repository.first_free_index());
+		
+		for index in [capacity - 1, capacity / 2, capacity / 3]
+		{
+			repository.remove(index);
+			assert_eq!(Some(index), repository.first_free_index());
+		}
+		
+		assert_eq!(Some(capacity / 3), repository.first_free_index());
+		assert_eq!(capacity / 3, repository.insert(0));
+	}
+}
+
+#[test]
+fn test_get_or_insert_with()
+{
+	let mut repository = Repository::<String>::new();
+	let mut calls = 0;
+	
+	assert_eq!("5", repository.get_or_insert_with(5, || {calls += 1; String::from("5")}));
+	assert_eq!(1, calls);
+	assert_eq!(1, repository.len());
+	assert!(repository.capacity() > 5);
+	
+	repository.get_or_insert_with(5, || {calls += 1; String::new()}).push('!');
+	assert_eq!(1, calls);
+	assert_eq!("5!", repository[5]);
+	
+	repository.get_or_insert_with(1000, || String::from("1000"));
+	repository.get_or_insert_with(129, || String::from("129"));
+	assert!(repository.index_iter().eq([5, 129, 1000]));
+	assert_eq!(3, repository.len());
+	
+	// Regular insertion fills the lowest holes around the explicitly placed values
+	assert_eq!(0, repository.insert(String::from("0")));
+	repository.remove(5);
+	assert_eq!(1, repository.insert(String::from("1")));
+	assert_eq!("x", repository.get_or_insert_with(5, || String::from("x")));
+	assert_eq!(2, repository.insert(String::from("2")));
+	assert_eq!(6, repository.len());
+}
+
+#[test]
+fn test_clone_from_reuses_allocation()
+{
+	let mut source = Repository::<String>::new();
+	for i in 0 .. 500
+	{
+		source.insert(i.to_string());
+	}
+	for i in (0 .. 500).step_by(4)
+	{
+		source.remove(i);
+	}
+	
+	for capacity in [source.capacity(), 500, 2000]
+	{
+		let mut target = Repository::<String>::with_capacity(capacity);
+		target.fill_with(|| String::from("old"));
+		let data = target.storage.data;
+		
+		target.clone_from(&source);
+		assert_eq!(data, target.storage.data);
+		assert_eq!(capacity, target.capacity());
+		assert!(target.iter_indexed().eq(source.iter_indexed()));
+		assert_eq!(source.peek_next_index(), target.peek_next_index());
+	}
+	
+	let mut target = Repository::<String>::with_capacity(10);
+	target.clone_from(&source);
+	assert!(target.iter_indexed().eq(source.iter_indexed()));
+}
+
+#[test]
+fn test_for_each_mut()
+{
+	let mut repository = Repository::<usize>::new();
+	for i in 0 .. 1000
+	{
+		repository.insert(i);
+	}
+	for i in (0 .. 1000).step_by(3).chain(128 .. 256)
+	{
+		repository.remove(i);
+	}
+	
+	let mut visited = Vec::new();
+	repository.for_each_mut(|index, value|
+	{
+		visited.push(index);
+		*value = *value * 2 + index;
+	});
+	assert!(visited.iter().copied().eq(repository.index_iter()));
+	assert!(repository.iter_indexed().all(|(index, value)| *value == index * 3));
+	
+	for value in repository.iter_mut()
+	{
+		*value += 1;
+	}
+	assert!(repository.iter_indexed().all(|(index, value)| *value == index * 3 + 1));
+	
+	Repository::<usize>::new().for_each_mut(|_, _| panic!());
+}
+
+#[test]
+fn test_leaf_bitset()
+{
+	assert!(Repository::<i32>::new().leaf_bitset().is_empty());
+	
+	let mut repository = Repository::<i32>::new();
+	for i in 0 .. 1000
+	{
+		repository.insert(i);
+	}
+	for i in (0 .. 1000).filter(|i| i % 5 != 0)
+	{
+		repository.remove(i);
+	}
+	
+	let bitset = repository.leaf_bitset();
+	assert_eq!(bit_indexing::level_length(repository.capacity()), bitset.len());
+	assert_eq!(repository.len(), bitset.iter().map(|word| word.count_ones() as usize).sum::<usize>());
+	assert!(bit_indexing::IndexSliceIterator::new(bitset).eq(repository.index_iter()));
+	assert_eq!(&repository.occupied_mask()[..], bitset);
+}
+
+#[test]
+fn test_recycle_into()
+{
+	let mut repository = Repository::<u64>::new();
+	for i in 0 .. 1000
+	{
+		repository.insert(i);
+	}
+	for i in (0 .. 1000).step_by(2)
+	{
+		repository.remove(i);
+	}
+	repository.set_capacity_limit(Some(2000));
+	let capacity = repository.capacity();
+	let data = repository.storage.data;
+	
+	let mut recycled = repository.recycle_into::<[u32; 2]>();
+	assert_eq!(data, recycled.storage.data);
+	assert_eq!(capacity, recycled.capacity());
+	assert_eq!(Some(2000), recycled.capacity_limit());
+	assert!(recycled.is_empty());
+	assert_eq!(None, recycled.get(1));
+	
+	for i in 0 .. capacity as u32
+	{
+		assert_eq!(i as usize, recycled.insert([i, i + 1]));
+	}
+	assert_eq!(data, recycled.storage.data);
+	assert!(recycled.iter_indexed().all(|(i, v)| *v == [i as u32, i as u32 + 1]));
+	
+	// Values with a different size need a new allocation, the old values are dropped
+	let counter = std::rc::Rc::new(());
+	let mut repository = Repository::<std::rc::Rc<()>>::new();
+	for _ in 0 .. 100
+	{
+		repository.insert(counter.clone());
+	}
+	let recycled = repository.recycle_into::<[u8; 3]>();
+	assert_eq!(1, std::rc::Rc::strong_count(&counter));
+	assert!(recycled.capacity() >= 100);
+	assert!(recycled.is_empty());
+	
+	assert_eq!(0, Repository::<u64>::new().recycle_into::<String>().capacity());
+}
+
+#[cfg(test)]
+fn fuzz_repository(seed: u64, operations: usize)
+{
+	use rand::{Rng, SeedableRng};
+	let mut rng = rand::rngs::SmallRng::seed_from_u64(seed);
+	
+	let mut repository = Repository::<u64>::new();
+	let mut model = std::collections::HashMap::<usize, u64>::new();
+	
+	for operation in 0 .. operations
+	{
+		let bound = repository.capacity() + 8;
+		
+		match rng.gen_range(0 .. 16)
+		{
+			0 ..= 6 =>
+			{
+				let value = rng.gen::<u64>();
+				let index = repository.insert(value);
+				assert_eq!(None, model.insert(index, value));
+			}
+			7 ..= 10 =>
+			{
+				let index = rng.gen_range(0 .. bound);
+				assert_eq!(model.remove(&index), repository.remove(index));
+			}
+			11 ..= 12 =>
+			{
+				let index = rng.gen_range(0 .. bound);
+				if let Some(value) = repository.get_mut(index)
+				{
+					*value = value.wrapping_add(1);
+					*model.get_mut(&index).unwrap() += 1;
+				}
+				assert_eq!(model.get(&index), repository.get(index));
+			}
+			13 =>
+			{
+				repository.shrink_to_fit();
+			}
+			14 =>
+			{
+				let mut copy = Repository::<u64>::with_capacity(rng.gen_range(0 .. bound));
+				copy.insert(rng.gen());
+				copy.clone_from(&repository);
+				repository = copy;
+			}
+			_ =>
+			{
+				repository.retain(|value| value % 8 != 0);
+				model.retain(|_, value| *value % 8 != 0);
+			}
+		}
+		
+		assert_eq!(model.len(), repository.len());
+		assert_eq!(model.len(), repository.iter().len());
+		
+		if operation % 32 != 0
+		{
+			continue;
+		}
+		
+		let mut indices = model.keys().copied().collect::<Vec<_>>();
+		indices.sort_unstable();
+		assert!(repository.index_iter().eq(indices.iter().copied()));
+		assert!(repository.iter_indexed().eq(indices.iter().map(|i| (*i, &model[i]))));
+		
+		for index in 0 .. repository.capacity() + 2
+		{
+			assert_eq!(model.get(&index), repository.get(index));
+		}
+	}
+}
+
+#[test]
+fn test_fuzz()
+{
+	fuzz_repository(0, 20_000);
+	fuzz_repository(1, 20_000);
+}
+
+/// Long-running variant, run with `cargo test --release -- --ignored test_fuzz_long`.
+/// The number of seeds can be set with the `VAULTS_FUZZ_SEEDS` environment variable.
+#[test]
+#[ignore]
+fn test_fuzz_long()
+{
+	let seeds = std::env::var("VAULTS_FUZZ_SEEDS").ok().and_then(|s| s.parse().ok()).unwrap_or(16);
+	for seed in 0 .. seeds
+	{
+		fuzz_repository(seed, 50_000);
+	}
+}
@@ -0,0 +1,172 @@
+use crate::svst::repository::Repository;
+
+/// A slab allocator, a thin wrapper of [Repository] using the vocabulary of the `slab` crate.
+/// 
+/// Keys returned by `insert` remain valid until the value is removed.
+/// The keys of removed values are reused by subsequent insertions.
+#[derive(Debug)]
+pub struct Slab<Type>
+{
+	repository: Repository<Type>,
+}
+
+/// A handle to a vacant slot of a [Slab] which knows its key before the value is inserted.
+pub struct VacantEntry<'t, Type>
+{
+	key: usize,
+	slab: &'t mut Slab<Type>,
+}
+
+impl<Type> Slab<Type>
+{
+	/// Constructs a new, empty `Slab<Type>`.
+	pub const fn new() -> Self
+	{
+		Self {repository: Repository::new()}
+	}
+	
+	/// Constructs a new, empty `Slab<Type>` with at least the specified capacity.
+	pub fn with_capacity(capacity: usize) -> Self
+	{
+		Self {repository: Repository::with_capacity(capacity)}
+	}
+	
+	/// Returns the total number of values the slab can hold without reallocating.
+	pub fn capacity(&self) -> usize {self.repository.capacity()}
+	
+	/// Returns the number of values in the slab.
+	pub fn len(&self) -> usize {self.repository.len()}
+	
+	/// Returns `true` if the slab contains no values.
+	pub fn is_empty(&self) -> bool {self.repository.is_empty()}
+	
+	/// Clears the slab, removing all values.
+	pub fn clear(&mut self) {self.repository.clear()}
+	
+	/// Inserts a value in the slab returning its key.
+	pub fn insert(&mut self, value: Type) -> usize
+	{
+		self.repository.insert(value)
+	}
+	
+	/// Removes the value associated with `key`, returning it or [None].
+	pub fn remove(&mut self, key: usize) -> Option<Type>
+	{
+		self.repository.remove(key)
+	}
+	
+	/// Returns `true` if a value is associated with `key`.
+	pub fn contains(&self, key: usize) -> bool
+	{
+		self.repository.get(key).is_some()
+	}
+	
+	pub fn get(&self, key: usize) -> Option<&Type>
+	{
+		self.repository.get(key)
+	}
+	
+	pub fn get_mut(&mut self, key: usize) -> Option<&mut Type>
+	{
+		self.repository.get_mut(key)
+	}
+	
+	/// Returns an iterator over the pairs of keys and values present in the slab.
+	pub fn iter(&self) -> impl std::iter::Iterator<Item = (usize, &Type)>
+	{
+		self.repository.iter_indexed()
+	}
+	
+	/// Returns a handle to the slot which will be occupied by the next inserted value.
+	pub fn vacant_entry(&mut self) -> VacantEntry<'_, Type>
+	{
+		VacantEntry
+		{
+			key: self.repository.peek_next_index(),
+			slab: self,
+		}
+	}
+}
+
+impl<'t, Type> VacantEntry<'t, Type>
+{
+	/// Returns the key the inserted value will be associated with.
+	pub fn key(&self) -> usize {self.key}
+	
+	/// Inserts a value in the slot, returning a mutable reference to it.
+	pub fn insert(self, value: Type) -> &'t mut Type
+	{
+		let key = self.slab.repository.insert(value);
+		debug_assert_eq!(self.key, key);
+		return &mut self.slab.repository[key];
+	}
+}
+
+impl<Type> Default for Slab<Type>
+{
+	fn default() -> Self {Self::new()}
+}
+
+impl<Type> std::ops::Index<usize> for Slab<Type>
+{
+	type Output = Type;
+	
+	fn index(&self, key: usize) -> &Self::Output
+	{
+		&self.repository[key]
+	}
+}
+
+impl<Type> std::ops::IndexMut<usize> for Slab<Type>
+{
+	fn index_mut(&mut self, key: usize) -> &mut Self::Output
+	{
+		&mut self.repository[key]
+	}
+}
+
+#[test]
+fn test_slab()
+{
+	let mut slab = Slab::new();
+	let hello = slab.insert("hello");
+	let world = slab.insert("world");
+	
+	assert_eq!(0, hello);
+	assert_eq!(1, world);
+	assert_eq!(2, slab.len());
+	assert_eq!("hello", slab[hello]);
+	assert_eq!(Some(&"world"), slab.get(world));
+	assert!(slab.contains(world));
+	
+	assert_eq!(Some("hello"), slab.remove(hello));
+	assert!(! slab.contains(hello));
+	assert_eq!(None, slab.remove(hello));
+	assert_eq!(hello, slab.insert("again"));
+	assert_eq!(2, slab.insert("more"));
+	
+	assert!([(0, &"again"), (1, &"world"), (2, &"more")].into_iter().eq(slab.iter()));
+}
+
+#[test]
+fn test_slab_vacant_entry()
+{
+	let mut slab = Slab::new();
+	
+	for i in 0 .. 200
+	{
+		let entry = slab.vacant_entry();
+		let key = entry.key();
+		assert_eq!(i, key);
+		assert_eq!(&mut (key, "value"), entry.insert((key, "value")));
+	}
+	
+	slab.remove(150);
+	slab.remove(20);
+	
+	let entry = slab.vacant_entry();
+	assert_eq!(20, entry.key());
+	entry.insert((20, "reused"));
+	assert_eq!((20, "reused"), slab[20]);
+	assert_eq!(150, slab.vacant_entry().key());
+}
@@ -18,7 +18,7 @@ impl VectorStorage
 	
 	pub fn default_capacity_growth(capacity: usize) -> usize
 	{
-		8 + capacity + (capacity + 1) / 2
+		capacity.saturating_add(8 + capacity / 2 + capacity % 2)
 	}
 	
 	pub fn default_capacity_for(mut start: usize, capacity: usize) -> usize
@@ -31,5 +31,6 @@ impl VectorStorage
 	}
 }
 
+// The storage is untyped, the owners of the values hold the PhantomData which restricts these
 unsafe impl Send for VectorStorage {}
 unsafe impl Sync for VectorStorage {}